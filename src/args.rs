@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::{command, Parser, ValueEnum};
+use clap::{Parser, ValueEnum};
 
 #[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
 pub enum Mode {
@@ -8,6 +8,87 @@ pub enum Mode {
     Format,
     Compress,
     Upload,
+    /// Check templates like format mode without modifying them
+    Check,
+    /// Restore formatted templates to their pre-format state
+    Revert,
+    /// Format, compress and upload every template in sequence
+    All,
+}
+
+impl Mode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Format => "format",
+            Mode::Compress => "compress",
+            Mode::Upload => "upload",
+            Mode::Check => "check",
+            Mode::Revert => "revert",
+            Mode::All => "all",
+        }
+    }
+
+    /// Name of the first stage of mode.
+    pub fn stage(&self) -> &'static str {
+        match self {
+            Mode::Format | Mode::All => "format",
+            Mode::Compress => "compress",
+            Mode::Upload => "upload",
+            Mode::Check => "check",
+            Mode::Revert => "revert",
+        }
+    }
+}
+
+/// Run report file format.
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    /// JUnit XML
+    Junit,
+}
+
+/// Log line format.
+#[derive(Default, Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Reset stylesheet position relative to template stylesheet.
+#[derive(Default, Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum ResetOrder {
+    /// Template stylesheet overrides reset stylesheet
+    #[default]
+    Before,
+    /// Reset stylesheet overrides template stylesheet
+    After,
+}
+
+/// What format mode does with templates that already formatted.
+#[derive(Default, Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Existing {
+    /// Leave formatted templates untouched
+    #[default]
+    Skip,
+    /// Validate formatted templates without modifying them
+    Check,
+    /// Format again from original zip, refuse when the folder has been edited
+    Reformat,
+}
+
+/// What format mode does with scripts, event handlers and embedded documents.
+#[derive(Default, Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Sanitize {
+    /// Remove active content that is not allowed, and report it
+    #[default]
+    Strip,
+    /// Fail the template when it contains active content that is not allowed
+    Reject,
 }
 
 /// HTML Template processer.
@@ -33,4 +114,76 @@ pub struct Args {
     /// Upload filename prefix. file `A002_GG42_1100X600.zip` if set name then will use name `[name]_GG42`, otherwise will use orginal name `A002_GG42_1100X600.zip` .
     #[arg(short = 'n', long = "name")]
     pub upload_name: Option<String>,
+    /// Print what would be done without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Stop at the first failed template
+    #[arg(long)]
+    pub fail_fast: bool,
+    /// Write result of every template to report file
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+    /// Report file format. default by report file extension, `.csv`, `.xml` or json
+    #[arg(long, value_enum, requires = "report")]
+    pub report_format: Option<ReportFormat>,
+    #[command(flatten)]
+    pub log: LogOptions,
+    #[command(flatten)]
+    pub format: FormatOptions,
+}
+
+/// Log options.
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "Log")]
+pub struct LogOptions {
+    /// Show more logs, `-v` for steps of every template, `-vv` for every file
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Only show errors and the summary
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Log line format
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+}
+
+/// Format mode options.
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "Format")]
+pub struct FormatOptions {
+    /// Format annotation rules file, toml or json. default [directory]/eris.rules.toml
+    #[arg(short, long)]
+    pub rules: Option<PathBuf>,
+    /// Wrap template in a root element with unique class, and scope all styles under it.
+    #[arg(long)]
+    pub scope: bool,
+    /// Custom reset stylesheet file. default built-in reset stylesheet
+    #[arg(long, conflicts_with = "no_reset")]
+    pub reset: Option<PathBuf>,
+    /// Do not add reset stylesheet.
+    #[arg(long)]
+    pub no_reset: bool,
+    /// Reset stylesheet position relative to template stylesheet.
+    #[arg(long, value_enum, default_value_t = ResetOrder::Before)]
+    pub reset_order: ResetOrder,
+    /// Rewrite local asset paths to public base url or prefix,
+    /// `{name}` will be replaced with template name. such as `https://cdn.com/{name}/`
+    #[arg(long)]
+    pub asset_base: Option<String>,
+    /// Embed images, fonts and CSS `url()` assets as base64 data URIs.
+    #[arg(long)]
+    pub embed_assets: bool,
+    /// Assets larger than this size in bytes stay external when embedding.
+    #[arg(long, default_value_t = 100 * 1024)]
+    pub embed_max_size: u64,
+    /// Thumbnail width, height is calculated from the template size.
+    #[arg(long, default_value_t = 400)]
+    pub thumb_width: u32,
+    /// What to do with scripts, event handlers, `javascript:` urls and iframes
+    /// that are not allowed by `[sanitize]` in rules file.
+    #[arg(long, value_enum, default_value_t = Sanitize::Strip)]
+    pub sanitize: Sanitize,
+    /// What to do with templates that already formatted.
+    #[arg(long, value_enum, default_value_t = Existing::Skip)]
+    pub existing: Existing,
 }
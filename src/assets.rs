@@ -0,0 +1,263 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ego_tree::NodeId;
+use scraper::{ElementRef, Html, Node};
+
+use crate::{
+    css::{self, Stylesheet},
+    dom::add_attr,
+    utils::{is_local_url, normalize, url_path},
+};
+
+/// Attributes that reference an asset.
+const ASSET_ATTRS: [&str; 3] = ["src", "poster", "data-src"];
+/// Asset attributes that browser loads, `data-src` is the asset path for the editor.
+const RENDERED_ATTRS: [&str; 2] = ["src", "poster"];
+
+/// Collect all local asset references of template.
+///
+/// Include asset attributes, `srcset`, inline styles and stylesheet `url()`.
+/// Returned paths are relative to template folder, without query and fragment.
+///
+/// - `doc`: parsed document
+/// - `body`: body element node id
+/// - `stylesheet`: template stylesheet
+pub fn references(doc: &Html, body: NodeId, stylesheet: &Stylesheet) -> Result<Vec<String>> {
+    let body = doc
+        .tree
+        .get(body)
+        .and_then(ElementRef::wrap)
+        .ok_or(anyhow!("cannot find body element"))?;
+
+    let mut urls = vec![];
+    for element in body.descendants().filter_map(ElementRef::wrap) {
+        let value = element.value();
+        for attr in ASSET_ATTRS {
+            if let Some(url) = value.attr(attr) {
+                urls.push(url.to_string());
+            }
+        }
+        if let Some(srcset) = value.attr("srcset") {
+            urls.extend(srcset_urls(srcset).into_iter().map(String::from));
+        }
+        if let Some(style) = value.attr("style") {
+            urls.extend(css::urls(style));
+        }
+    }
+    urls.extend(stylesheet.urls());
+
+    let mut result: Vec<String> = vec![];
+    for url in urls.iter().filter(|url| is_local_url(url)) {
+        let path = normalize(Path::new(url_path(url)))
+            .to_string_lossy()
+            .replace('\\', "/");
+        if !result.contains(&path) {
+            result.push(path);
+        }
+    }
+    Ok(result)
+}
+
+/// Find references that do not exist.
+///
+/// - `references`: asset references
+/// - `exists`: detect the referenced asset exists
+pub fn missing(references: &[String], exists: impl Fn(&str) -> bool) -> Vec<&str> {
+    references
+        .iter()
+        .filter(|path| !exists(path))
+        .map(String::as_str)
+        .collect()
+}
+
+/// Rewrite all local asset references of template that browser loads.
+///
+/// `data-src` is kept as is, so the editor still gets asset paths.
+///
+/// - `doc`: parsed document
+/// - `body`: body element node id
+/// - `stylesheet`: template stylesheet
+/// - `rewrite`: return new url of a local url, `None` keeps the url
+pub fn rewrite(
+    doc: &mut Html,
+    body: NodeId,
+    stylesheet: &mut Stylesheet,
+    rewrite: &impl Fn(&str) -> Option<String>,
+) -> Result<()> {
+    let local = |url: &str| {
+        if is_local_url(url) {
+            rewrite(url)
+        } else {
+            None
+        }
+    };
+    stylesheet.rewrite_urls(&local);
+
+    let body = doc
+        .tree
+        .get(body)
+        .and_then(ElementRef::wrap)
+        .ok_or(anyhow!("cannot find body element"))?;
+    let mut targets = vec![];
+    for element in body.descendants().filter_map(ElementRef::wrap) {
+        let value = element.value();
+        for attr in RENDERED_ATTRS {
+            if let Some(url) = value.attr(attr).and_then(local) {
+                targets.push((element.id(), attr, url));
+            }
+        }
+        if let Some(srcset) = value.attr("srcset") {
+            let new = rewrite_srcset(srcset, &local);
+            if new != srcset {
+                targets.push((element.id(), "srcset", new));
+            }
+        }
+        if let Some(style) = value.attr("style") {
+            let new = css::rewrite_urls(style, &local);
+            if new != style {
+                targets.push((element.id(), "style", new));
+            }
+        }
+    }
+
+    for (id, attr, value) in targets {
+        let mut node = doc
+            .tree
+            .get_mut(id)
+            .ok_or(anyhow!("cannot find node {:?}", id))?;
+        if let Node::Element(element) = node.value() {
+            add_attr(element, attr, &value);
+        }
+    }
+    Ok(())
+}
+
+/// Join asset path to public base url.
+///
+/// `("https://cdn.com/tpl/", "img/a.png")` => `https://cdn.com/tpl/img/a.png`
+pub fn join_base(base: &str, path: &str) -> String {
+    let path = normalize(Path::new(path))
+        .to_string_lossy()
+        .replace('\\', "/");
+    format!("{}/{}", base.trim_end_matches('/'), path)
+}
+
+/// Read local asset as base64 data URI.
+///
+/// Return `None` when the asset is missing, unknown type or larger than `max_size` bytes.
+///
+/// - `folder`: template folder
+/// - `url`: local asset url
+/// - `max_size`: max asset size in bytes
+pub fn data_uri(folder: &Path, url: &str, max_size: u64) -> Option<String> {
+    let path = folder.join(normalize(Path::new(url_path(url))));
+    let mime = mime_type(&path)?;
+    let size = fs::metadata(&path).ok()?.len();
+    if size > max_size {
+        return None;
+    }
+    let content = fs::read(&path).ok()?;
+    Some(format!("data:{};base64,{}", mime, STANDARD.encode(content)))
+}
+
+/// Mime type of asset by extension.
+fn mime_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    let mime = match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "bmp" => "image/bmp",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// Urls in `srcset` attribute.
+///
+/// `a.png 1x, b.png 2x` => `["a.png", "b.png"]`
+fn srcset_urls(srcset: &str) -> Vec<&str> {
+    srcset
+        .split(',')
+        .filter_map(|candidate| candidate.split_whitespace().next())
+        .collect()
+}
+
+/// Rewrite url of every `srcset` candidate, descriptors are kept.
+///
+/// `a.png 1x, b.png 2x` => `https://cdn.com/a.png 1x, https://cdn.com/b.png 2x`
+fn rewrite_srcset(srcset: &str, rewrite: &impl Fn(&str) -> Option<String>) -> String {
+    srcset
+        .split(',')
+        .map(|candidate| {
+            let start = candidate.len() - candidate.trim_start().len();
+            let rest = &candidate[start..];
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            match rewrite(&rest[..end]) {
+                Some(url) => format!("{}{}{}", &candidate[..start], url, &rest[end..]),
+                None => candidate.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::Selector;
+
+    use super::*;
+
+    #[test]
+    fn rewrite_keeps_data_src() {
+        let mut doc = Html::parse_document(
+            r#"<body><img src="a.png" data-src="a.png"><div data-src="b.png" style="background: url(b.png)"></div></body>"#,
+        );
+        let body = doc
+            .select(&Selector::parse("body").unwrap())
+            .next()
+            .unwrap()
+            .id();
+        let mut stylesheet = Stylesheet::parse(".c { background: url(c.png) }");
+        assert_eq!(
+            references(&doc, body, &stylesheet).unwrap(),
+            vec!["a.png", "b.png", "c.png"]
+        );
+
+        rewrite(&mut doc, body, &mut stylesheet, &|url| {
+            Some(format!("https://cdn.com/{}", url))
+        })
+        .unwrap();
+        let html = doc.root_element().html();
+        assert!(html.contains(r#"<img src="https://cdn.com/a.png" data-src="a.png">"#));
+        assert!(html.contains(
+            r#"data-src="b.png" style="background: url(&quot;https://cdn.com/b.png&quot;)""#
+        ));
+        assert_eq!(stylesheet.urls(), vec!["https://cdn.com/c.png"]);
+    }
+
+    #[test]
+    fn rewrite_srcset_by_candidate() {
+        let rewrite = |url: &str| Some(format!("https://cdn/X_Y_100x100/{}", url));
+        assert_eq!(
+            rewrite_srcset("a.png 1x, aa.png 2x,a.png", &rewrite),
+            "https://cdn/X_Y_100x100/a.png 1x, https://cdn/X_Y_100x100/aa.png 2x,https://cdn/X_Y_100x100/a.png"
+        );
+        let rewrite =
+            |url: &str| (url == "a.png").then(|| "data:image/png;base64,AA==".to_string());
+        assert_eq!(
+            rewrite_srcset("aa.png 2x, a.png 1x", &rewrite),
+            "aa.png 2x, data:image/png;base64,AA== 1x"
+        );
+    }
+}
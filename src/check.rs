@@ -0,0 +1,222 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{
+    assets, dom,
+    manifest::Manifest,
+    rules::Rules,
+    styles, thumb,
+    utils::{normalize, template_size},
+    ziper::Ziper,
+};
+
+/// Check template zip like format mode, without writing anything to disk.
+///
+/// Return all problems found, empty if the template is ready to format.
+///
+/// - `path`: template zip
+/// - `ziper`: ziper
+/// - `rules`: annotation rules
+pub fn check(path: &Path, ziper: &Ziper, rules: &Rules) -> Vec<String> {
+    let mut problems = vec![];
+    if let Err(err) = check_template(path, ziper, rules, &mut problems) {
+        problems.push(format!("{:#}", err));
+    }
+    problems
+}
+
+fn check_template(
+    path: &Path,
+    ziper: &Ziper,
+    rules: &Rules,
+    problems: &mut Vec<String>,
+) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or(anyhow!("cannot read target filename"))?
+        .to_string_lossy();
+    let name = file_name.strip_suffix(".zip").unwrap_or(&file_name);
+    let name_regex = Regex::new(r"^[^_]+_[^_]+_\d+[xX]\d+$")?;
+    if !name_regex.is_match(name) {
+        problems.push(format!(
+            "filename {} does not match CODE_GROUP_WxH",
+            file_name
+        ));
+    }
+    let size = template_size(name);
+
+    // thumbnail that has same name with zip
+    let folder = path.with_file_name(name);
+    match thumb::find_sibling(&folder) {
+        None => problems.push(format!("missing thumbnail {}.jpg", name)),
+        Some(thumb_path) => match thumb::decode(&thumb_path) {
+            Err(err) => problems.push(format!("{:#}", err)),
+            Ok(img) => {
+                let (w, h) = (img.width(), img.height());
+                if let Some(size) = size.filter(|s| !thumb::ratio_matches((w, h), *s)) {
+                    problems.push(format!(
+                        "thumbnail size {}x{} does not match template size {}x{}",
+                        w, h, size.0, size.1
+                    ));
+                }
+            }
+        },
+    }
+
+    let entries = ziper.entries(path)?;
+    let read = |p: &Path| {
+        let key = normalize(p).to_string_lossy().replace('\\', "/");
+        entries
+            .get(&key)
+            .map(|content| String::from_utf8_lossy(content).into_owned())
+            .ok_or(io::Error::new(io::ErrorKind::NotFound, "not found in zip"))
+    };
+    let Ok(index) = read(&PathBuf::from("index.html")) else {
+        problems.push("missing index.html".to_string());
+        return Ok(());
+    };
+    let has_style = entries.contains_key("style.css");
+    if !has_style {
+        problems.push("missing style.css".to_string());
+    }
+    // `<body>` is always created by the parser
+    if !index.to_ascii_lowercase().contains("<body") {
+        problems.push("missing <body> in index.html".to_string());
+    }
+
+    let doc = Html::parse_document(&index);
+    let stylesheet = match styles::collect(&doc, &read) {
+        Ok(styles) => {
+            problems.extend(styles.warnings);
+            styles.stylesheet
+        }
+        Err(err) => {
+            if has_style {
+                problems.push(format!("{:#}", err));
+            }
+            return Ok(());
+        }
+    };
+    let body_selector =
+        Selector::parse("body").map_err(|err| anyhow!("cannot create selector {}", err))?;
+    let body = doc
+        .select(&body_selector)
+        .next()
+        .ok_or(anyhow!("select target {:?} failed", body_selector))?;
+
+    problems.extend(dom::validate_annotations(&body, &rules.kinds));
+
+    let references = assets::references(&doc, body.id(), &stylesheet)?;
+    for asset in assets::missing(&references, |p| entries.contains_key(p)) {
+        problems.push(format!("broken asset reference {}", asset));
+    }
+
+    if let Some((width, height)) = size {
+        check_root_size(&body, &stylesheet, (width, height), problems);
+    }
+    Ok(())
+}
+
+/// Check template that already formatted, without writing anything to disk.
+///
+/// Return all problems found, empty if the template is vaild.
+///
+/// - `read`: read file in formatted template, such as `template.html`
+pub fn check_formatted(read: &dyn Fn(&str) -> Option<Vec<u8>>) -> Vec<String> {
+    let mut problems = vec![];
+    let Some(template) = read("template.html") else {
+        problems.push("missing template.html".to_string());
+        return problems;
+    };
+    let doc = Html::parse_document(&String::from_utf8_lossy(&template));
+    let selector = Selector::parse("[data-template]").expect("selector must be vaild");
+    let mut ids = HashSet::new();
+    for element in doc.select(&selector) {
+        match element.value().attr("data-id") {
+            Some(id) => {
+                ids.insert(id.to_string());
+            }
+            None => problems.push(format!(
+                "<{}> has data-template but no data-id",
+                element.value().name()
+            )),
+        }
+        let kind = element.value().attr("data-template").unwrap_or_default();
+        if !dom::is_container(kind) && element.select(&selector).next().is_some() {
+            problems.push(format!(
+                "{} field {} contains other editable elements",
+                kind,
+                element.value().attr("data-id").unwrap_or_default()
+            ));
+        }
+    }
+    if ids.is_empty() {
+        problems.push("no editable element in template.html".to_string());
+    }
+
+    match read("template.json").map(|json| serde_json::from_slice::<Manifest>(&json)) {
+        None => problems.push("missing template.json".to_string()),
+        Some(Err(err)) => problems.push(format!("cannot parse template.json {}", err)),
+        Some(Ok(manifest)) => {
+            let fields = manifest
+                .fields
+                .iter()
+                .map(|f| f.id.as_str())
+                .collect::<HashSet<_>>();
+            let mut unlisted = ids
+                .iter()
+                .filter(|id| !fields.contains(id.as_str()))
+                .collect::<Vec<_>>();
+            unlisted.sort();
+            for id in unlisted {
+                problems.push(format!("field {} is not in template.json", id));
+            }
+            for field in manifest.fields.iter().filter(|f| !ids.contains(&f.id)) {
+                problems.push(format!("field {} is not in template.html", field.id));
+            }
+        }
+    }
+
+    match read("thumb.jpg") {
+        None => problems.push("missing thumb.jpg".to_string()),
+        Some(thumb) if image::load_from_memory(&thumb).is_err() => {
+            problems.push("thumb.jpg is corrupt".to_string())
+        }
+        _ => {}
+    }
+    problems
+}
+
+/// Compare the root container size with the template size.
+fn check_root_size(
+    body: &ElementRef,
+    stylesheet: &crate::css::Stylesheet,
+    size: (u32, u32),
+    problems: &mut Vec<String>,
+) {
+    let Some(root) = body.children().find_map(ElementRef::wrap) else {
+        problems.push("missing root container in <body>".to_string());
+        return;
+    };
+    let (root_width, root_height) = dom::root_size(body, &root, stylesheet);
+    for (label, declared, expected) in [
+        ("width", root_width, size.0),
+        ("height", root_height, size.1),
+    ] {
+        match declared {
+            None => problems.push(format!("root container {} is not declared", label)),
+            Some(v) if (v - expected as f32).abs() > 0.5 => problems.push(format!(
+                "root container {} {}px does not match filename {} {}px",
+                label, v, label, expected
+            )),
+            _ => {}
+        }
+    }
+}
@@ -1,4 +1,6 @@
 pub static RESET_CSS: &str = r#"html,body,div,span,applet,object,iframe,h1,h2,h3,h4,h5,h6,p,blockquote,pre,a,abbr,acronym,address,big,cite,code,del,dfn,em,img,ins,kbd,q,s,samp,small,strike,strong,sub,sup,tt,var,b,u,i,center,dl,dt,dd,ol,ul,li,fieldset,form,label,legend,table,caption,tbody,tfoot,thead,tr,th,td,article,aside,canvas,details,embed,figure,figcaption,footer,header,hgroup,menu,nav,output,ruby,section,summary,time,mark,audio,video{margin:0;padding:0;border:0;font-size:100%;font:inherit;vertical-align:baseline}article,aside,details,figcaption,figure,footer,header,hgroup,menu,nav,section{display:block}body{line-height:1}ol,ul{list-style:none}blockquote,q{quotes:none}blockquote:before,blockquote:after,q:before,q:after{content:'';content:none}table{border-collapse:collapse;border-spacing:0}"#;
+// Rules file names find in target directory
+pub const RULES_FILES: [&str; 2] = ["eris.rules.toml", "eris.rules.json"];
 pub static BASE_URL: &str = "http://183.162.254.169:8086/";
 
 // Zip method
@@ -6,3 +8,9 @@ pub const METHOD_STORED: Option<zip::CompressionMethod> = Some(zip::CompressionM
 // Skip files when zip;
 pub const SKIP_FILES: [&str; 3] = ["globals.css", "style.css", ".DS_Store"];
 pub const SKIP_FOLDERS: [&str; 1] = ["static"];
+// Original files of formatted template are kept in this folder
+pub const BACKUP_FOLDER: &str = ".eris";
+// Files generated by format mode
+pub const GENERATED_FILES: [&str; 3] = ["template.html", "template.json", "thumb.jpg"];
+// Content hash of every file in formatted folder, kept in backup folder
+pub const CHECKSUMS_FILE: &str = "checksums.json";
@@ -0,0 +1,777 @@
+use std::fmt;
+
+/// Minimal CSS stylesheet.
+///
+/// Only split stylesheet into rules and declarations, values are kept as is.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stylesheet {
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    /// `.title { font-size: 12px }`
+    Rule(StyleRule),
+    /// `@media screen { ... }`, `@import "a.css";`
+    AtRule(AtRule),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleRule {
+    /// Selector list, such as `.title, h1`
+    pub selectors: String,
+    pub declarations: Vec<Declaration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    /// Property name in lowercase
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtRule {
+    /// At-rule name without `@` in lowercase, such as `media`
+    pub name: String,
+    /// Everything between name and block, such as `screen and (max-width: 100px)`
+    pub prelude: String,
+    pub block: Option<Block>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// Nested rules of conditional at-rules, such as `@media`
+    Rules(Vec<Item>),
+    /// Other blocks are kept as is, such as `@font-face` and `@keyframes`
+    Raw(String),
+}
+
+/// At-rules that contain nested style rules.
+const NESTED_AT_RULES: [&str; 4] = ["media", "supports", "document", "layer"];
+
+impl Stylesheet {
+    pub fn parse(css: &str) -> Self {
+        Self {
+            items: parse_items(css),
+        }
+    }
+
+    /// Iterate all style rules, include rules nested in at-rules.
+    pub fn rules(&self) -> Vec<&StyleRule> {
+        let mut rules = vec![];
+        collect_rules(&self.items, &mut rules);
+        rules
+    }
+
+    /// Iterate all style rules mutably, include rules nested in at-rules.
+    pub fn rules_mut(&mut self) -> Vec<&mut StyleRule> {
+        let mut rules = vec![];
+        collect_rules_mut(&mut self.items, &mut rules);
+        rules
+    }
+
+    /// Find all `url()` references in stylesheet.
+    pub fn urls(&self) -> Vec<String> {
+        let mut urls = vec![];
+        collect_urls(&self.items, &mut urls);
+        urls
+    }
+
+    /// Rewrite all `url()` references in stylesheet.
+    ///
+    /// - `rewrite`: return new url, `None` keeps the url
+    pub fn rewrite_urls(&mut self, rewrite: &impl Fn(&str) -> Option<String>) {
+        rewrite_items(&mut self.items, rewrite);
+    }
+
+    /// Remove declarations whose property `other` already declares for the same selector,
+    /// rules that become empty are removed.
+    ///
+    /// Selectors are compared one by one, a rule is split when its selectors keep
+    /// different declarations. Only top level rules are compared.
+    ///
+    /// `html, body { margin: 0; padding: 0 }` with `body { margin: 8px }`
+    /// => `html { margin: 0; padding: 0 } body { padding: 0 }`
+    pub fn remove_duplicates(&mut self, other: &Stylesheet) {
+        // property names declared for each selector
+        let mut declared: Vec<(String, &str)> = vec![];
+        for item in &other.items {
+            let Item::Rule(rule) = item else {
+                continue;
+            };
+            for selector in rule.normalized_selectors() {
+                for declaration in &rule.declarations {
+                    declared.push((selector.clone(), &declaration.name));
+                }
+            }
+        }
+
+        let mut items = vec![];
+        for item in self.items.drain(..) {
+            let Item::Rule(rule) = item else {
+                items.push(item);
+                continue;
+            };
+            // group selectors that keep the same declarations, in original order
+            let mut groups: Vec<(Vec<&str>, Vec<Declaration>)> = vec![];
+            for (selector, normalized) in rule
+                .selector_list()
+                .into_iter()
+                .zip(rule.normalized_selectors())
+            {
+                let kept = rule
+                    .declarations
+                    .iter()
+                    .filter(|d| {
+                        !declared
+                            .iter()
+                            .any(|(s, name)| *s == normalized && *name == d.name)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if kept.is_empty() {
+                    continue;
+                }
+                match groups
+                    .iter_mut()
+                    .find(|(_, declarations)| *declarations == kept)
+                {
+                    Some((selectors, _)) => selectors.push(selector),
+                    None => groups.push((vec![selector], kept)),
+                }
+            }
+            if let [(_, declarations)] = groups.as_slice() {
+                if *declarations == rule.declarations {
+                    items.push(Item::Rule(rule));
+                    continue;
+                }
+            }
+            items.extend(groups.into_iter().map(|(selectors, declarations)| {
+                Item::Rule(StyleRule {
+                    selectors: selectors.join(", "),
+                    declarations,
+                })
+            }));
+        }
+        self.items = items;
+    }
+
+    /// Rewrite every selector under `root` selector.
+    ///
+    /// `html` and `body` selectors are rewritten to target `root` itself.
+    ///
+    /// `.title, body.dark p` => `.root .title, .root.dark p`
+    pub fn scope(&mut self, root: &str) {
+        for rule in self.rules_mut() {
+            let mut selectors: Vec<String> = vec![];
+            for selector in rule.selector_list() {
+                let selector = scope_selector(selector, root);
+                if !selectors.contains(&selector) {
+                    selectors.push(selector);
+                }
+            }
+            rule.selectors = selectors.join(", ");
+        }
+    }
+}
+
+fn collect_rules<'a>(items: &'a [Item], rules: &mut Vec<&'a StyleRule>) {
+    for item in items {
+        match item {
+            Item::Rule(rule) => rules.push(rule),
+            Item::AtRule(AtRule {
+                block: Some(Block::Rules(items)),
+                ..
+            }) => collect_rules(items, rules),
+            _ => {}
+        }
+    }
+}
+
+fn collect_urls(items: &[Item], result: &mut Vec<String>) {
+    for item in items {
+        match item {
+            Item::Rule(rule) => {
+                for declaration in &rule.declarations {
+                    result.extend(urls(&declaration.value));
+                }
+            }
+            Item::AtRule(rule) => match &rule.block {
+                Some(Block::Rules(items)) => collect_urls(items, result),
+                Some(Block::Raw(raw)) => result.extend(urls(raw)),
+                None => {}
+            },
+        }
+    }
+}
+
+fn rewrite_items(items: &mut [Item], rewrite: &impl Fn(&str) -> Option<String>) {
+    for item in items {
+        match item {
+            Item::Rule(rule) => {
+                for declaration in rule.declarations.iter_mut() {
+                    declaration.value = rewrite_urls(&declaration.value, rewrite);
+                }
+            }
+            Item::AtRule(rule) => match &mut rule.block {
+                Some(Block::Rules(items)) => rewrite_items(items, rewrite),
+                Some(Block::Raw(raw)) => *raw = rewrite_urls(raw, rewrite),
+                None => {}
+            },
+        }
+    }
+}
+
+fn collect_rules_mut<'a>(items: &'a mut [Item], rules: &mut Vec<&'a mut StyleRule>) {
+    for item in items {
+        match item {
+            Item::Rule(rule) => rules.push(rule),
+            Item::AtRule(AtRule {
+                block: Some(Block::Rules(items)),
+                ..
+            }) => collect_rules_mut(items, rules),
+            _ => {}
+        }
+    }
+}
+
+/// Strip leading type selector `name` from selector.
+///
+/// `body.dark p` => `Some(".dark p")`, `bodyx` => `None`
+fn strip_type<'a>(selector: &'a str, name: &str) -> Option<&'a str> {
+    let rest = selector
+        .get(..name.len())
+        .filter(|s| s.eq_ignore_ascii_case(name))
+        .map(|_| &selector[name.len()..])?;
+    let vaild = rest
+        .chars()
+        .next()
+        .is_none_or(|c| !(c.is_alphanumeric() || c == '-' || c == '_'));
+    vaild.then_some(rest)
+}
+
+/// Rewrite a single selector under `root` selector.
+fn scope_selector(selector: &str, root: &str) -> String {
+    let mut rest = selector;
+    let mut document = false;
+    for name in ["html", ":root"] {
+        if let Some(r) = strip_type(rest, name) {
+            rest = r.trim_start().trim_start_matches('>').trim_start();
+            document = true;
+        }
+    }
+    if let Some(r) = strip_type(rest, "body") {
+        return format!("{}{}", root, r);
+    }
+    if rest.is_empty() && document {
+        return root.to_string();
+    }
+    format!("{} {}", root, rest)
+}
+
+impl StyleRule {
+    /// Split selector list by top level comma.
+    pub fn selector_list(&self) -> Vec<&str> {
+        split_top_level(&self.selectors, ',')
+            .into_iter()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    /// Selector list with collapsed whitespace, used to compare rules.
+    fn normalized_selectors(&self) -> Vec<String> {
+        self.selector_list()
+            .into_iter()
+            .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect()
+    }
+}
+
+/// Parse declarations in a block or an inline `style` attribute.
+///
+/// `color: red; background: url(a.png)`
+pub fn parse_declarations(block: &str) -> Vec<Declaration> {
+    let block = strip_comments(block);
+    split_top_level(&block, ';')
+        .into_iter()
+        .filter_map(|decl| {
+            let (name, value) = decl.split_once(':')?;
+            let name = name.trim();
+            let value = value.trim();
+            if name.is_empty() || value.is_empty() {
+                return None;
+            }
+            Some(Declaration {
+                name: name.to_lowercase(),
+                value: value.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Find all `url()` references in a declaration value.
+///
+/// `url("img/a.png") no-repeat` => `["img/a.png"]`
+pub fn urls(value: &str) -> Vec<String> {
+    let lower = value.to_ascii_lowercase();
+    let mut result = vec![];
+    let mut start = 0;
+    while let Some(i) = lower[start..].find("url(") {
+        let begin = start + i + 4;
+        let Some(end) = value[begin..].find(')') else {
+            break;
+        };
+        let url = value[begin..begin + end]
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .trim();
+        if !url.is_empty() {
+            result.push(url.to_string());
+        }
+        start = begin + end;
+    }
+    result
+}
+
+/// Rewrite all `url()` references in target.
+///
+/// - `target`: declaration value or raw block
+/// - `rewrite`: return new url, `None` keeps the url
+pub fn rewrite_urls(target: &str, rewrite: &impl Fn(&str) -> Option<String>) -> String {
+    let lower = target.to_ascii_lowercase();
+    let mut result = String::with_capacity(target.len());
+    let mut start = 0;
+    while let Some(i) = lower[start..].find("url(") {
+        let begin = start + i + 4;
+        let Some(end) = target[begin..].find(')') else {
+            break;
+        };
+        let url = target[begin..begin + end]
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .trim();
+        result.push_str(&target[start..begin]);
+        match rewrite(url) {
+            Some(new) => result.push_str(&format!("\"{}\"", new)),
+            None => result.push_str(&target[begin..begin + end]),
+        }
+        start = begin + end;
+    }
+    result.push_str(&target[start..]);
+    result
+}
+
+/// Find the background image url in declarations.
+///
+/// The last `background` or `background-image` declaration wins,
+/// `background: none` means no background image.
+pub fn background_url(declarations: &[Declaration]) -> Option<Option<String>> {
+    declarations
+        .iter()
+        .rev()
+        .find(|d| d.name == "background" || d.name == "background-image")
+        .map(|d| urls(&d.value).into_iter().next())
+}
+
+/// Find the font size in px in declarations.
+///
+/// The last `font-size` or `font` declaration wins, `rem` and `em` are
+/// treated as 16px based, relative keywords are ignored.
+pub fn font_size(declarations: &[Declaration]) -> Option<f32> {
+    let decl = declarations
+        .iter()
+        .rev()
+        .find(|d| d.name == "font-size" || d.name == "font")?;
+    decl.value
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .find_map(parse_length)
+}
+
+/// Find length in px of the last declaration with target name.
+///
+/// `width: 1100px` => `Some(1100.0)`
+pub fn length(declarations: &[Declaration], name: &str) -> Option<f32> {
+    let decl = declarations.iter().rev().find(|d| d.name == name)?;
+    parse_length(&decl.value)
+}
+
+/// Parse length to px, such as `12px`, `1.5rem`, `9pt`.
+fn parse_length(value: &str) -> Option<f32> {
+    let value = value.trim().to_lowercase();
+    let units = [("px", 1.0), ("rem", 16.0), ("em", 16.0), ("pt", 4.0 / 3.0)];
+    units.iter().find_map(|(unit, ratio)| {
+        let number = value.strip_suffix(unit)?.parse::<f32>().ok()?;
+        Some(number * ratio)
+    })
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut result = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(i) = rest.find("/*") {
+        result.push_str(&rest[..i]);
+        rest = match rest[i + 2..].find("*/") {
+            Some(end) => &rest[i + 2 + end + 2..],
+            None => "",
+        };
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Split target by separator that not in strings, parentheses or blocks.
+fn split_top_level(target: &str, separator: char) -> Vec<&str> {
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    let mut chars = target.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, c) if c == separator && depth == 0 => {
+                result.push(&target[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    result.push(&target[start..]);
+    result
+}
+
+/// Find the first `{` or `;` at top level, return its index.
+fn find_block_start(target: &str) -> Option<(usize, char)> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut chars = target.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth -= 1,
+            (None, '{' | ';') if depth == 0 => return Some((i, c)),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Find the `}` that matches the `{` before `target`, return its index.
+fn find_block_end(target: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut chars = target.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') if depth == 0 => return Some(i),
+            (None, '}') => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_items(css: &str) -> Vec<Item> {
+    let css = strip_comments(css);
+    let mut items = vec![];
+    let mut rest = css.as_str();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let Some((i, c)) = find_block_start(rest) else {
+            break;
+        };
+        let prelude = rest[..i].trim();
+        let (block, next) = if c == '{' {
+            let body = &rest[i + 1..];
+            let end = find_block_end(body).unwrap_or(body.len());
+            (Some(&body[..end]), body.get(end + 1..).unwrap_or(""))
+        } else {
+            (None, &rest[i + 1..])
+        };
+        rest = next;
+
+        if let Some(at) = prelude.strip_prefix('@') {
+            let (name, prelude) = at
+                .split_once(|c: char| c.is_whitespace())
+                .unwrap_or((at, ""));
+            let name = name.to_lowercase();
+            let block = block.map(|block| {
+                if NESTED_AT_RULES.contains(&name.as_str()) {
+                    Block::Rules(parse_items(block))
+                } else {
+                    Block::Raw(block.trim().to_string())
+                }
+            });
+            items.push(Item::AtRule(AtRule {
+                name,
+                prelude: prelude.trim().to_string(),
+                block,
+            }));
+        } else if let Some(block) = block {
+            items.push(Item::Rule(StyleRule {
+                selectors: prelude.to_string(),
+                declarations: parse_declarations(block),
+            }));
+        }
+    }
+    items
+}
+
+impl fmt::Display for Stylesheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.items {
+            writeln!(f, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Rule(rule) => write!(f, "{}", rule),
+            Item::AtRule(rule) => write!(f, "{}", rule),
+        }
+    }
+}
+
+impl fmt::Display for StyleRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let declarations = self
+            .declarations
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}{{{}}}", self.selectors, declarations.join(";"))
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.name, self.value)
+    }
+}
+
+impl fmt::Display for AtRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@{}", self.name)?;
+        if !self.prelude.is_empty() {
+            write!(f, " {}", self.prelude)?;
+        }
+        match &self.block {
+            None => write!(f, ";"),
+            Some(Block::Raw(raw)) => write!(f, "{{{}}}", raw),
+            Some(Block::Rules(items)) => {
+                write!(f, "{{")?;
+                for item in items {
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(selectors: &str, declarations: &[(&str, &str)]) -> Item {
+        Item::Rule(StyleRule {
+            selectors: selectors.to_string(),
+            declarations: declarations
+                .iter()
+                .map(|(name, value)| Declaration {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn parse_rules_and_at_rules() {
+        let css = r#"
+            /* comment { } */
+            @charset "utf-8";
+            .a, .b > p { COLOR: red; background: url("a;b.png") }
+            @media (max-width: 100px) { .c { margin: 0 } }
+            @font-face { font-family: x; src: url(x.woff) }
+        "#;
+        let items = parse_items(css);
+        assert_eq!(
+            items,
+            vec![
+                Item::AtRule(AtRule {
+                    name: "charset".to_string(),
+                    prelude: r#""utf-8""#.to_string(),
+                    block: None,
+                }),
+                rule(
+                    ".a, .b > p",
+                    &[("color", "red"), ("background", r#"url("a;b.png")"#)]
+                ),
+                Item::AtRule(AtRule {
+                    name: "media".to_string(),
+                    prelude: "(max-width: 100px)".to_string(),
+                    block: Some(Block::Rules(vec![rule(".c", &[("margin", "0")])])),
+                }),
+                Item::AtRule(AtRule {
+                    name: "font-face".to_string(),
+                    prelude: String::new(),
+                    block: Some(Block::Raw("font-family: x; src: url(x.woff)".to_string())),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_unclosed_block() {
+        let items = parse_items(".a { color: red");
+        assert_eq!(items, vec![rule(".a", &[("color", "red")])]);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let css = "@import \"a.css\";\n.a{color:red}\n@media print{.b{margin:0}}\n";
+        assert_eq!(Stylesheet::parse(css).to_string(), css);
+    }
+
+    #[test]
+    fn split_ignores_nested_separators() {
+        assert_eq!(
+            split_top_level(r#"a, b:is(c, d), [title="e,f"], g"#, ','),
+            vec!["a", " b:is(c, d)", r#" [title="e,f"]"#, " g"]
+        );
+        assert_eq!(
+            split_top_level(r#"a: url("x;y"); b: "\";""#, ';'),
+            vec![r#"a: url("x;y")"#, r#" b: "\";""#]
+        );
+    }
+
+    #[test]
+    fn find_urls() {
+        assert_eq!(
+            urls(r#"url("a.png"), URL( 'b.png' ) no-repeat, url(c.png), url()"#),
+            vec!["a.png", "b.png", "c.png"]
+        );
+        assert!(urls("none").is_empty());
+    }
+
+    #[test]
+    fn rewrite_only_returned_urls() {
+        let rewritten = rewrite_urls("url(a.png), url('keep.png')", &|url| {
+            (url != "keep.png").then(|| format!("img/{}", url))
+        });
+        assert_eq!(rewritten, r#"url("img/a.png"), url('keep.png')"#);
+    }
+
+    #[test]
+    fn rewrite_stylesheet_urls() {
+        let mut stylesheet = Stylesheet::parse(
+            ".a { background: url(a.png) } @font-face { src: url(x.woff) } @media print { .b { background: url(b.png) } }",
+        );
+        stylesheet.rewrite_urls(&|url| Some(format!("css/{}", url)));
+        assert_eq!(
+            stylesheet.urls(),
+            vec!["css/a.png", "css/x.woff", "css/b.png"]
+        );
+    }
+
+    #[test]
+    fn scope_selectors() {
+        let root = ".root";
+        assert_eq!(scope_selector(".title", root), ".root .title");
+        assert_eq!(scope_selector("body", root), ".root");
+        assert_eq!(scope_selector("body.dark p", root), ".root.dark p");
+        assert_eq!(scope_selector("html", root), ".root");
+        assert_eq!(scope_selector("html body", root), ".root");
+        assert_eq!(scope_selector(":root", root), ".root");
+        assert_eq!(scope_selector("html > .a", root), ".root .a");
+        assert_eq!(scope_selector("bodyx", root), ".root bodyx");
+    }
+
+    #[test]
+    fn scope_stylesheet() {
+        let mut stylesheet =
+            Stylesheet::parse(".a, body { margin: 0 } @media print { p { color: red } }");
+        stylesheet.scope(".root");
+        assert_eq!(
+            stylesheet.to_string(),
+            ".root .a, .root{margin:0}\n@media print{.root p{color:red}}\n"
+        );
+    }
+
+    #[test]
+    fn remove_duplicates_by_selector_and_property() {
+        let template = Stylesheet::parse("body { margin: 8px } p, h1 { color: red }");
+        let mut reset = Stylesheet::parse(
+            "html, body { margin: 0; padding: 0 } h1 { color: black } \
+             p,  h1 { color: blue } @media print { body { margin: 0 } }",
+        );
+        reset.remove_duplicates(&template);
+        assert_eq!(
+            reset.items,
+            vec![
+                rule("html", &[("margin", "0"), ("padding", "0")]),
+                rule("body", &[("padding", "0")]),
+                Stylesheet::parse("@media print { body { margin: 0 } }").items[0].clone(),
+            ]
+        );
+    }
+
+    #[test]
+    fn remove_duplicates_keeps_untouched_rules() {
+        let template = Stylesheet::parse(".a { color: red }");
+        let mut reset = Stylesheet::parse("html,\nbody { margin: 0 }");
+        let expected = reset.clone();
+        reset.remove_duplicates(&template);
+        assert_eq!(reset, expected);
+    }
+
+    #[test]
+    fn lengths() {
+        let declarations =
+            parse_declarations("font: bold 1.5rem/2 serif; width: 12pt; height: auto");
+        assert_eq!(font_size(&declarations), Some(24.0));
+        assert_eq!(length(&declarations, "width"), Some(16.0));
+        assert_eq!(length(&declarations, "height"), None);
+    }
+
+    #[test]
+    fn background() {
+        let declarations = parse_declarations("background-image: url(a.png); background: none");
+        assert_eq!(background_url(&declarations), Some(None));
+        let declarations = parse_declarations("background: #fff url(b.png)");
+        assert_eq!(
+            background_url(&declarations),
+            Some(Some("b.png".to_string()))
+        );
+        assert_eq!(background_url(&parse_declarations("color: red")), None);
+    }
+}
@@ -0,0 +1,705 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use ego_tree::NodeId;
+use html5ever::{
+    local_name, namespace_url, ns,
+    tendril::{fmt::UTF8, Tendril},
+    LocalName, Namespace, QualName,
+};
+use scraper::{
+    node::{Element, Text},
+    ElementRef, Html, Node, Selector,
+};
+
+use crate::{
+    css::{self, Declaration, Stylesheet},
+    rules::{Action, Rule, Rules},
+};
+
+/// Elements with this attribute and their children are never annotated.
+pub const IGNORE_ATTR: &str = "data-eris-ignore";
+
+/// Background image path of elements.
+pub type Backgrounds = HashMap<NodeId, String>;
+/// Declared font size in px of elements.
+pub type FontSizes = HashMap<NodeId, f32>;
+
+/// Build dom attributes
+///
+/// - `attr` attribute name
+/// - `value` attirbute value
+///
+/// `data-template="img"`: `attr_builder("data-template", "img");`
+pub fn attr_builder<'a>(attr: &'a str, value: &'a str) -> (QualName, Tendril<UTF8>) {
+    let data = QualName {
+        prefix: None,
+        ns: Namespace::from(""),
+        local: LocalName::from(attr),
+    };
+    let data_v: Tendril<UTF8> = Tendril::from(value);
+    (data, data_v)
+}
+
+/// Add attributes to target element
+///
+/// - `element` target element
+/// - `attr` attribute
+/// - `value` attribute value
+///
+/// `<div data-template="img"></div>`: `add_attr(div_element, "data-template", "img");`
+pub fn add_attr<'a>(element: &mut Element, attr: &'a str, value: &'a str) {
+    let (data_img, data_v) = attr_builder(attr, value);
+    element.attrs.insert(data_img, data_v);
+}
+
+/// Add attributes to the element with `id` in the document tree.
+///
+/// - `doc` parsed document
+/// - `id` target element node id
+/// - `attrs` attribute name and value pairs
+fn annotate_node(doc: &mut Html, id: NodeId, attrs: &[(&str, &str)]) -> Result<()> {
+    let mut node = doc
+        .tree
+        .get_mut(id)
+        .ok_or(anyhow!("cannot find node {:?}", id))?;
+    let Node::Element(element) = node.value() else {
+        return Err(anyhow!("node {:?} is not an element", id));
+    };
+    for (attr, value) in attrs {
+        add_attr(element, attr, value);
+    }
+    Ok(())
+}
+
+/// Detect trget text is vaild text
+///
+/// <div class="text-wrapper">人才发展</div>
+pub fn vaild_text(text: &str) -> bool {
+    let t = text.split('\n').collect::<Vec<_>>();
+    t.iter().any(|text| !text.trim().is_empty())
+}
+
+/// Collect a declared value of every element under `body`.
+///
+/// Inline `style` attributes override rules in stylesheet,
+/// the later rule overrides the former.
+///
+/// - `body` body element
+/// - `stylesheet` template stylesheet
+/// - `value` find value in declarations, `Some(None)` resets the value
+fn declared<T: Clone>(
+    body: &ElementRef,
+    stylesheet: &Stylesheet,
+    value: impl Fn(&[Declaration]) -> Option<Option<T>>,
+) -> HashMap<NodeId, T> {
+    let mut result = HashMap::new();
+    for rule in stylesheet.rules() {
+        let Some(v) = value(&rule.declarations) else {
+            continue;
+        };
+        for selector in rule.selector_list() {
+            // Pseudo elements and unsupported selectors
+            let Ok(selector) = Selector::parse(selector) else {
+                continue;
+            };
+            for element in body.select(&selector) {
+                result.insert(element.id(), v.clone());
+            }
+        }
+    }
+    for element in body.descendants().filter_map(ElementRef::wrap) {
+        let Some(style) = element.value().attr("style") else {
+            continue;
+        };
+        if let Some(v) = value(&css::parse_declarations(style)) {
+            result.insert(element.id(), v);
+        }
+    }
+    result
+        .into_iter()
+        .filter_map(|(id, v)| Some((id, v?)))
+        .collect()
+}
+
+/// Find elements that draw a background image under `body`.
+pub fn backgrounds(body: &ElementRef, stylesheet: &Stylesheet) -> Backgrounds {
+    declared(body, stylesheet, css::background_url)
+}
+
+/// Find elements that declare a font size under `body`.
+pub fn font_sizes(body: &ElementRef, stylesheet: &Stylesheet) -> FontSizes {
+    declared(body, stylesheet, |declarations| {
+        css::font_size(declarations).map(Some)
+    })
+}
+
+/// Declared width and height in px of the `root` element under `body`.
+pub fn root_size(
+    body: &ElementRef,
+    root: &ElementRef,
+    stylesheet: &Stylesheet,
+) -> (Option<f32>, Option<f32>) {
+    let widths = declared(body, stylesheet, |d| {
+        d.iter()
+            .any(|d| d.name == "width")
+            .then(|| css::length(d, "width"))
+    });
+    let heights = declared(body, stylesheet, |d| {
+        d.iter()
+            .any(|d| d.name == "height")
+            .then(|| css::length(d, "height"))
+    });
+    (
+        widths.get(&root.id()).copied(),
+        heights.get(&root.id()).copied(),
+    )
+}
+
+/// Add data attributes to elements under `body` by annotation rules.
+///
+/// Target nodes are collected first, then mutated in the document tree,
+/// so every element is annotated exactly once. Attributes that element already
+/// has, such as `data-title` set by designer, are never overwritten.
+///
+/// Editable regions never nest, except `bg` regions that contain the editable
+/// content drawn on the background, see [`is_container`]. A text element that
+/// contains other targets is not annotated, its bare text runs are wrapped in
+/// `<span>` and annotated instead. Text of `bg` element is wrapped the same way.
+/// Elements inside other editable regions, such as a `qrcode`, are not annotated.
+///
+/// `<p>Price: <b>99</b></p>` => `<p><span data-template="text">Price: </span><b data-template="text">99</b></p>`
+///
+/// - `doc` parsed document
+/// - `body` body element node id
+/// - `rules` annotation rules
+/// - `stylesheet` template stylesheet
+pub fn annotate(
+    doc: &mut Html,
+    body: NodeId,
+    rules: &Rules,
+    stylesheet: &Stylesheet,
+) -> Result<()> {
+    let body_ref = doc
+        .tree
+        .get(body)
+        .and_then(ElementRef::wrap)
+        .ok_or(anyhow!("cannot find body element"))?;
+    let backgrounds = backgrounds(&body_ref, stylesheet);
+    let font_sizes = font_sizes(&body_ref, stylesheet);
+
+    let problems = validate_annotations(&body_ref, &rules.kinds);
+    if !problems.is_empty() {
+        return Err(anyhow!(
+            "invalid annotations:\n  - {}",
+            problems.join("\n  - ")
+        ));
+    }
+
+    let mut targets = HashMap::new();
+    for element in body_ref.descendants().filter_map(ElementRef::wrap) {
+        if ignored(&element) {
+            continue;
+        }
+        if element.value().attr("data-template").is_some() {
+            targets.insert(element.id(), Target::Designer);
+            continue;
+        }
+        // fields of designer have no editable children, except `bg`
+        let in_field = element.ancestors().filter_map(ElementRef::wrap).any(|e| {
+            e.value()
+                .attr("data-template")
+                .is_some_and(|k| !is_container(k))
+        });
+        if in_field {
+            continue;
+        }
+        if let Some(rule) = rules.find(&element, &backgrounds) {
+            if rule.action == Action::Annotate {
+                targets.insert(element.id(), Target::Rule(rule));
+            }
+        }
+    }
+
+    // fields of rules have no editable children either, text fields are handled below
+    let fields = targets
+        .iter()
+        .filter_map(|(id, target)| match target {
+            Target::Rule(rule) => {
+                let kind = rule.attrs.get("data-template")?;
+                (kind != "text" && !is_container(kind)).then_some(*id)
+            }
+            Target::Designer => None,
+        })
+        .collect::<HashSet<_>>();
+    targets.retain(|id, _| {
+        doc.tree
+            .get(*id)
+            .is_some_and(|node| !node.ancestors().any(|a| fields.contains(&a.id())))
+    });
+
+    // text runs that need their own `<span>`, the element that contains them,
+    // and the text rule of the spans
+    let mut runs = vec![];
+    let mut parents = HashMap::new();
+    let mut mixed = vec![];
+    for (id, target) in &targets {
+        let Target::Rule(rule) = target else {
+            continue;
+        };
+        let Some(element) = doc.tree.get(*id).and_then(ElementRef::wrap) else {
+            continue;
+        };
+        let text_rule = if is_text(rule) {
+            // text that contains other editable elements is not annotated itself
+            let nested = element
+                .descendants()
+                .skip(1)
+                .any(|node| targets.contains_key(&node.id()));
+            if !nested {
+                continue;
+            }
+            mixed.push(*id);
+            *rule
+        } else {
+            // text of element annotated by other kind, such as `bg`
+            let Some(text_rule) = rules.find_text(&element, &backgrounds) else {
+                continue;
+            };
+            text_rule
+        };
+        for child in element.children() {
+            if child.value().as_text().is_some_and(|text| vaild_text(text)) {
+                runs.push((child.id(), *id));
+            }
+        }
+        parents.insert(*id, text_rule);
+    }
+    for id in mixed {
+        targets.remove(&id);
+    }
+    let mut wrapped = HashMap::new();
+    for (text, parent) in runs {
+        let span = wrap_text(doc, text)?;
+        wrapped.insert(span, parent);
+    }
+
+    let body_ref = doc
+        .tree
+        .get(body)
+        .and_then(ElementRef::wrap)
+        .ok_or(anyhow!("cannot find body element"))?;
+    let annotations = body_ref
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter_map(|element| {
+            let (rule, parent) = match wrapped.get(&element.id()) {
+                Some(parent) => (
+                    *parents.get(parent)?,
+                    doc.tree.get(*parent).and_then(ElementRef::wrap),
+                ),
+                None => match targets.get(&element.id())? {
+                    Target::Rule(rule) => (*rule, None),
+                    Target::Designer => {
+                        let attrs = designer_attrs(&element, rules, &backgrounds, &font_sizes);
+                        let has_id = element.value().attr("data-id").is_some();
+                        return Some((element.id(), node_path(&element), attrs, !has_id));
+                    }
+                },
+            };
+            let mut attrs = rule
+                .attrs
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Vec<_>>();
+            if rule.background {
+                if let Some(url) = backgrounds.get(&element.id()) {
+                    attrs.push(("data-src".to_string(), url.clone()));
+                }
+            }
+            if is_text(rule) && !rule.attrs.contains_key("data-title") {
+                let title = match parent {
+                    Some(parent) => {
+                        let text = element.text().collect::<String>();
+                        rules.labels.find_text(&parent, &text, &font_sizes)
+                    }
+                    None => rules.labels.find(&element, &font_sizes),
+                };
+                attrs.push(("data-title".to_string(), title.to_string()));
+            }
+            // attributes that designer set without `data-template` are kept
+            attrs.retain(|(k, _)| element.value().attr(k).is_none());
+            let has_id = element.value().attr("data-id").is_some();
+            Some((element.id(), node_path(&element), attrs, !has_id))
+        })
+        .collect::<Vec<_>>();
+
+    // keep ids that designer provided
+    let mut ids = body_ref
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|e| targets.contains_key(&e.id()))
+        .filter_map(|e| e.value().attr("data-id"))
+        .map(String::from)
+        .collect::<HashSet<_>>();
+    for (id, path, mut attrs, new_id) in annotations {
+        if new_id {
+            attrs.push(("data-id".to_string(), stable_id(&path, &mut ids)));
+        }
+        let attrs = attrs
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect::<Vec<_>>();
+        annotate_node(doc, id, &attrs)?;
+    }
+    Ok(())
+}
+
+/// Where annotation of a target element comes from.
+enum Target<'r> {
+    /// Annotated by rule
+    Rule(&'r Rule),
+    /// Annotated by designer with `data-template`
+    Designer,
+}
+
+/// Attributes that designer annotation lacks.
+///
+/// `data-title` comes from the rule of the same kind, or labels for text;
+/// `data-src` comes from background image for `bg`.
+fn designer_attrs(
+    element: &ElementRef,
+    rules: &Rules,
+    backgrounds: &Backgrounds,
+    font_sizes: &FontSizes,
+) -> Vec<(String, String)> {
+    let value = element.value();
+    let kind = value.attr("data-template").unwrap_or_default();
+    let mut attrs = vec![];
+    if value.attr("data-title").is_none() {
+        let title = rules
+            .find(element, backgrounds)
+            .filter(|rule| rule.attrs.get("data-template").map(String::as_str) == Some(kind))
+            .and_then(|rule| rule.attrs.get("data-title"))
+            .map(String::as_str)
+            .or_else(|| (kind == "text").then(|| rules.labels.find(element, font_sizes)));
+        if let Some(title) = title {
+            attrs.push(("data-title".to_string(), title.to_string()));
+        }
+    }
+    if kind == "bg" && value.attr("data-src").is_none() {
+        if let Some(url) = backgrounds.get(&element.id()) {
+            attrs.push(("data-src".to_string(), url.clone()));
+        }
+    }
+    attrs
+}
+
+/// Detect element or any of its ancestors has `data-eris-ignore`.
+fn ignored(element: &ElementRef) -> bool {
+    std::iter::once(**element)
+        .chain(element.ancestors())
+        .filter_map(ElementRef::wrap)
+        .any(|e| e.value().attr(IGNORE_ATTR).is_some())
+}
+
+/// Validate annotations that designer already added under `body`.
+///
+/// Return problems: kinds not in `kinds`, text fields that contain other
+/// editable elements and duplicated `data-id`.
+///
+/// - `body` body element
+/// - `kinds` allowed `data-template` values
+pub fn validate_annotations(body: &ElementRef, kinds: &[String]) -> Vec<String> {
+    let mut problems = vec![];
+    let mut ids = HashSet::new();
+    let annotated = body
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|e| e.value().attr("data-template").is_some() && !ignored(e))
+        .collect::<Vec<_>>();
+    for element in &annotated {
+        let value = element.value();
+        let kind = value.attr("data-template").unwrap_or_default();
+        if !kinds.iter().any(|k| k == kind) {
+            problems.push(format!(
+                "data-template \"{}\" on {} is not one of {}",
+                kind,
+                describe(value),
+                kinds.join(", ")
+            ));
+        }
+        if !is_container(kind) {
+            let nested = annotated
+                .iter()
+                .any(|e| e.ancestors().any(|a| a.id() == element.id()));
+            if nested {
+                problems.push(format!(
+                    "{} field {} contains other editable elements",
+                    kind,
+                    describe(value)
+                ));
+            }
+        }
+        if let Some(id) = value.attr("data-id") {
+            if !ids.insert(id) {
+                problems.push(format!("data-id \"{}\" is duplicated", id));
+            }
+        }
+    }
+    problems
+}
+
+/// Short description of element, such as `<div id="a" class="b c">`.
+pub fn describe(element: &Element) -> String {
+    let mut description = format!("<{}", element.name());
+    for attr in ["id", "class", "src"] {
+        if let Some(value) = element.attr(attr) {
+            description.push_str(&format!(" {}=\"{}\"", attr, value));
+        }
+    }
+    description.push('>');
+    description
+}
+
+/// Detect editable region of `kind` may contain other editable elements.
+///
+/// Only `bg` is a container, text and images drawn on a background are edited
+/// on their own. Other kinds, such as `text` and `img`, never nest.
+pub fn is_container(kind: &str) -> bool {
+    kind == "bg"
+}
+
+/// Detect target rule annotates text elements.
+fn is_text(rule: &Rule) -> bool {
+    rule.attrs.get("data-template").is_some_and(|t| t == "text")
+}
+
+/// Wrap the text node with `id` in a new `<span>`.
+///
+/// Return node id of the `<span>`.
+fn wrap_text(doc: &mut Html, id: NodeId) -> Result<NodeId> {
+    let span = Element::new(QualName::new(None, ns!(html), local_name!("span")), vec![]);
+    let span = doc
+        .tree
+        .get_mut(id)
+        .ok_or(anyhow!("cannot find node {:?}", id))?
+        .insert_before(Node::Element(span))
+        .id();
+    doc.tree
+        .get_mut(span)
+        .ok_or(anyhow!("cannot find node {:?}", span))?
+        .append_id(id);
+    Ok(span)
+}
+
+/// Position path of target element in document.
+///
+/// Element indices of every ancestor, `0-2-1` is the second child element
+/// of the third child element of the first element.
+fn node_path(element: &ElementRef) -> String {
+    let mut path = std::iter::once(**element)
+        .chain(element.ancestors())
+        .filter(|node| node.value().is_element())
+        .map(|node| {
+            node.prev_siblings()
+                .filter(|n| n.value().is_element())
+                .count()
+        })
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>();
+    path.reverse();
+    path.join("-")
+}
+
+/// Deterministic id of an element from its node path.
+///
+/// The FNV-1a hash of the path, a suffix is added when the hash is already used.
+///
+/// - `path`: node path of the element
+/// - `ids`: ids already used in the template
+fn stable_id(path: &str, ids: &mut HashSet<String>) -> String {
+    let hash = path.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    let id = format!("{:08x}", hash as u32);
+    let mut unique = id.clone();
+    let mut suffix = 1;
+    while !ids.insert(unique.clone()) {
+        unique = format!("{}-{}", id, suffix);
+        suffix += 1;
+    }
+    unique
+}
+
+/// Turn `body` into the scoped root element of template.
+///
+/// Rename `body` to `div`, add `class` to it, and prepend a `<style>` with `styles`.
+///
+/// - `doc` parsed document
+/// - `body` body element node id
+/// - `class` unique class of the root element
+/// - `styles` scoped stylesheet
+pub fn scope(doc: &mut Html, body: NodeId, class: &str, styles: &str) -> Result<()> {
+    let mut node = doc
+        .tree
+        .get_mut(body)
+        .ok_or(anyhow!("cannot find body element"))?;
+    let Node::Element(element) = node.value() else {
+        return Err(anyhow!("node {:?} is not an element", body));
+    };
+    element.name = QualName::new(None, ns!(html), local_name!("div"));
+    let classes = match element.attr("class") {
+        Some(old) => format!("{} {}", class, old),
+        None => class.to_string(),
+    };
+    add_attr(element, "class", &classes);
+
+    let style = Element::new(QualName::new(None, ns!(html), local_name!("style")), vec![]);
+    node.prepend(Node::Element(style)).append(Node::Text(Text {
+        text: styles.into(),
+    }));
+    Ok(())
+}
+
+/// Serialize the element with `id` in the document tree.
+pub fn serialize(doc: &Html, id: NodeId) -> Result<String> {
+    let element = doc
+        .tree
+        .get(id)
+        .and_then(ElementRef::wrap)
+        .ok_or(anyhow!("cannot find element {:?}", id))?;
+    Ok(element.html())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Annotate `<body>` of `html` with default rules, return serialized body.
+    fn format(html: &str, css: &str) -> String {
+        let mut doc = Html::parse_document(html);
+        let selector = Selector::parse("body").unwrap();
+        let body = doc.select(&selector).next().unwrap().id();
+        annotate(&mut doc, body, &Rules::default(), &Stylesheet::parse(css)).unwrap();
+        serialize(&doc, body).unwrap()
+    }
+
+    fn count(html: &str, pattern: &str) -> usize {
+        html.matches(pattern).count()
+    }
+
+    #[test]
+    fn identical_tags_are_annotated_once() {
+        let html = format("<body><p>same</p><p>same</p><p>same</p></body>", "");
+        assert_eq!(count(&html, "data-template=\"text\""), 3);
+        let doc = Html::parse_fragment(&html);
+        let ids = doc
+            .select(&Selector::parse("p").unwrap())
+            .filter_map(|p| p.value().attr("data-id"))
+            .collect::<HashSet<_>>();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    fn attribute_order_does_not_matter() {
+        let html = format(
+            r#"<body><img alt="a" src="a.png"><img src="a.png" alt="a"></body>"#,
+            "",
+        );
+        assert_eq!(count(&html, "data-template=\"img\""), 2);
+    }
+
+    #[test]
+    fn debug_form_differs_from_html() {
+        // entities, void elements and attribute quoting are serialized differently
+        let html = format(
+            r#"<body><p title='say "hi"'>Tom &amp; Jerry&nbsp;<br/></p><img src=a.png></body>"#,
+            "",
+        );
+        assert_eq!(count(&html, "data-template=\"text\""), 1);
+        assert_eq!(count(&html, "data-template=\"img\""), 1);
+        assert!(html.contains("Tom &amp; Jerry&nbsp;<br>"));
+        assert!(html.contains(r#"title="say &quot;hi&quot;""#));
+    }
+
+    #[test]
+    fn annotate_output() {
+        let html = format(
+            r#"<body><div class="box"><h1>Title</h1><p>Price: <b>99</b></p><img src="img/a.png"><div class="bg"></div></div></body>"#,
+            ".bg { background-image: url(img/b.png) }",
+        );
+        let expected = [
+            r#"<body><div class="box">"#,
+            r#"<h1 data-template="text" data-title="主标题" data-id="e94fdd1d">Title</h1>"#,
+            r#"<p><span data-template="text" data-title="正文" data-id="e3c7f82f">Price: </span>"#,
+            r#"<b data-template="text" data-title="数字" data-id="e3c7f67c">99</b></p>"#,
+            r#"<img src="img/a.png" data-template="img" data-title="图片" data-id="e94fd9b7">"#,
+            r#"<div class="bg" data-template="bg" data-title="背景" data-src="img/b.png" data-id="e94fd804"></div>"#,
+            r#"</div></body>"#,
+        ];
+        assert_eq!(html, expected.concat());
+    }
+
+    #[test]
+    fn background_wins_over_text() {
+        let html = format(
+            r#"<body><div style="background-image:url(img/x.png)">hello</div></body>"#,
+            "",
+        );
+        let expected = [
+            r#"<body><div style="background-image:url(img/x.png)" data-template="bg" "#,
+            r#"data-title="背景" data-src="img/x.png" data-id=""#,
+        ];
+        assert!(html.starts_with(&expected.concat()), "{}", html);
+        assert!(html.contains(r#"<span data-template="text" data-title="正文""#));
+        assert!(html.contains(">hello</span></div>"));
+    }
+
+    #[test]
+    fn only_bg_contains_editable_elements() {
+        let html = format(
+            r#"<body><div style="background-image:url(x.png)"><img src="a.png"></div><div data-template="img"><p>c</p></div></body>"#,
+            "",
+        );
+        assert_eq!(count(&html, r#"data-template="bg""#), 1);
+        assert_eq!(count(&html, r#"data-template="img""#), 2);
+        assert_eq!(count(&html, r#"data-template="text""#), 0);
+
+        let doc = Html::parse_document(
+            r#"<body><div data-template="bg"><p data-template="text">a</p></div><div data-template="img"><p data-template="text">b</p></div></body>"#,
+        );
+        let kinds = Rules::default().kinds;
+        let problems = validate_annotations(&doc.root_element(), &kinds);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].starts_with("img field <div>"));
+    }
+
+    #[test]
+    fn annotate_is_stable() {
+        let source = r#"<body><div><h1>Title</h1><p>Text</p><img src="a.png"></div></body>"#;
+        assert_eq!(format(source, ""), format(source, ""));
+    }
+
+    #[test]
+    fn designer_annotations_are_kept() {
+        let html = format(
+            r#"<body><p data-template="text" data-id="keep">a <b>b</b></p><div data-eris-ignore><p>c</p></div></body>"#,
+            "",
+        );
+        assert_eq!(count(&html, "data-template"), 1);
+        assert!(html.contains(r#"data-id="keep""#));
+
+        let html = format(
+            r#"<body><img src="a.png" data-title="Logo"><p data-title="Slogan" data-id="s">a</p></body>"#,
+            "",
+        );
+        assert!(html.contains(r#"data-template="img""#));
+        assert!(html.contains(r#"data-title="Logo""#));
+        assert!(html.contains(r#"data-template="text""#));
+        assert!(html.contains(r#"data-title="Slogan""#));
+        assert_eq!(count(&html, "data-title"), 2);
+        assert!(html.contains(r#"data-id="s""#));
+        assert_eq!(count(&html, "data-id"), 2);
+    }
+}
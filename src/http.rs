@@ -1,8 +1,13 @@
-use std::path::Path;
+use std::{
+    fs::{self, File},
+    path::Path,
+};
 
 use anyhow::{anyhow, Context, Result};
+use indicatif::ProgressBar;
 use reqwest::blocking::{self, multipart};
 use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
 
 use crate::consts::BASE_URL;
 
@@ -24,7 +29,11 @@ impl<'a> Http<'a> {
             upload_url: format!("{}admin/Apitemplategrapic/add", base_url),
         }
     }
-    pub fn upload(&self, path: &Path, name_suffix: &'a Option<String>) -> Result<()> {
+    /// Multipart text fields that upload request of target file will send.
+    ///
+    /// - `path`: target zip file
+    /// - `name_suffix`: upload name prefix
+    pub fn form(&self, path: &Path, name_suffix: &'a Option<String>) -> Result<UploadForm> {
         // let file = fs::read(path)?;
         let filename = &path
             .file_name()
@@ -42,7 +51,7 @@ impl<'a> Http<'a> {
         };
         let upload_name = if let Some(name) = name_suffix.as_ref() {
             if up_name.len() < 3 {
-                eprintln!("Wraning: filename is illegal {}", &filename);
+                warn!("filename is illegal {}", &filename);
                 filename.to_string()
             } else {
                 format!("{}_{}", &name, &up_name[1])
@@ -64,17 +73,60 @@ impl<'a> Http<'a> {
             ("", "")
         };
 
-        println!(
-            "Starting upload {} as {} with width {} height {}",
-            &filename, &upload_name, &width, &height
+        Ok(UploadForm {
+            name: upload_name,
+            alias: filename.to_string(),
+            width: String::from(width),
+            height: String::from(height),
+        })
+    }
+
+    /// Print the request that upload target file would send.
+    pub fn plan(&self, path: &Path, name_suffix: &'a Option<String>) -> Result<()> {
+        let form = self.form(path, name_suffix)?;
+        let size = match fs::metadata(path) {
+            Ok(metadata) => format!("{} bytes", metadata.len()),
+            Err(_) => "not created yet".to_string(),
+        };
+        println!("Would upload {:?}", path);
+        println!("  POST {}", &self.upload_url);
+        println!("  header token: {}", mask(self.token));
+        println!("  field name: {}", &form.name);
+        println!("  field alias: {}", &form.alias);
+        println!("  field width: {}", &form.width);
+        println!("  field height: {}", &form.height);
+        println!("  field file: {:?} ({})", path, size);
+        Ok(())
+    }
+
+    /// Upload target file with form fields from [`Http::form`].
+    ///
+    /// - `sent`: bar that counts bytes of file sent
+    pub fn upload(&self, path: &Path, form: UploadForm, sent: &ProgressBar) -> Result<()> {
+        let UploadForm {
+            name: upload_name,
+            alias,
+            width,
+            height,
+        } = form;
+
+        debug!(
+            "start upload {} as {} with width {} height {}",
+            &alias, &upload_name, &width, &height
         );
 
+        let file = File::open(path).with_context(|| anyhow!("cannot open {:?}", path))?;
+        let size = file.metadata()?.len();
+        sent.set_length(size);
+        let part = multipart::Part::reader_with_length(sent.wrap_read(file), size)
+            .file_name(alias.clone())
+            .mime_str("application/zip")?;
         let form = multipart::Form::new()
             .text("name", upload_name.clone())
-            .text("alias", filename.to_string())
-            .text("width", String::from(width))
-            .text("height", String::from(height))
-            .file("file", path)?;
+            .text("alias", alias)
+            .text("width", width)
+            .text("height", height)
+            .part("file", part);
 
         let res = self
             .client
@@ -87,7 +139,7 @@ impl<'a> Http<'a> {
             .with_context(|| anyhow!("parse response failed"))?;
 
         if res.code == "200" {
-            println!("Upload {} succeeded", &upload_name);
+            debug!("upload {} succeeded", &upload_name);
         } else {
             return Err(anyhow!("Upload {} failed, {:?}", &upload_name, &res));
         }
@@ -96,6 +148,29 @@ impl<'a> Http<'a> {
     }
 }
 
+/// Multipart text fields of upload request, `file` field is the zip itself.
+#[derive(Debug)]
+pub struct UploadForm {
+    pub name: String,
+    pub alias: String,
+    pub width: String,
+    pub height: String,
+}
+
+/// Hide token except the first and last characters.
+fn mask(token: &str) -> String {
+    let chars = token.chars().collect::<Vec<_>>();
+    if chars.len() <= 4 {
+        return "*".repeat(chars.len());
+    }
+    format!(
+        "{}{}{}",
+        chars[..2].iter().collect::<String>(),
+        "*".repeat(chars.len() - 4),
+        chars[chars.len() - 2..].iter().collect::<String>()
+    )
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ResBody {
     code: String,
@@ -0,0 +1,174 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use scraper::{ElementRef, Selector};
+use serde::Deserialize;
+
+use crate::dom::FontSizes;
+
+/// A `data-title` labelling rule for text elements.
+///
+/// ```toml
+/// [[label]]
+/// selector = ".price"
+/// pattern = '^¥\d+$'
+/// min_font_size = 24
+/// title = "价格"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawLabel {
+    /// CSS selector
+    #[serde(default)]
+    pub selector: Option<String>,
+    /// Regex that the whole element text should match
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Minimum font size in px, inherited from ancestors
+    #[serde(default)]
+    pub min_font_size: Option<f32>,
+    /// `data-title` value
+    pub title: String,
+}
+
+#[derive(Debug)]
+pub struct Label {
+    selector: Option<Selector>,
+    pattern: Option<Regex>,
+    min_font_size: Option<f32>,
+    title: String,
+}
+
+impl TryFrom<RawLabel> for Label {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawLabel) -> Result<Self> {
+        let selector = raw
+            .selector
+            .map(|s| {
+                Selector::parse(&s).map_err(|err| anyhow!("cannot create selector {} {}", s, err))
+            })
+            .transpose()?;
+        let pattern = raw.pattern.map(|p| Regex::new(&p)).transpose()?;
+        Ok(Self {
+            selector,
+            pattern,
+            min_font_size: raw.min_font_size,
+            title: raw.title,
+        })
+    }
+}
+
+impl Label {
+    /// Detect target text element is matched by this label.
+    ///
+    /// - `element`: target element
+    /// - `text`: trimmed text of target element
+    /// - `font_sizes`: font sizes of elements
+    fn matches(&self, element: &ElementRef, text: &str, font_sizes: &FontSizes) -> bool {
+        if let Some(selector) = &self.selector {
+            if !selector.matches(element) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(text) {
+                return false;
+            }
+        }
+        if let Some(min) = self.min_font_size {
+            let size = std::iter::once(**element)
+                .chain(element.ancestors())
+                .find_map(|node| font_sizes.get(&node.id()));
+            if !size.is_some_and(|size| *size >= min) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Labelling rules, the first matched label wins.
+#[derive(Debug)]
+pub struct Labels {
+    labels: Vec<Label>,
+}
+
+impl Labels {
+    /// Create labels, custom labels are evaluated before default labels.
+    pub fn new(custom: Vec<RawLabel>) -> Result<Self> {
+        let labels = custom
+            .into_iter()
+            .chain(default_labels())
+            .map(Label::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { labels })
+    }
+
+    /// Find `data-title` for target text element.
+    pub fn find(&self, element: &ElementRef, font_sizes: &FontSizes) -> &str {
+        let text = element.text().collect::<String>();
+        self.find_text(element, &text, font_sizes)
+    }
+
+    /// Find `data-title` for `text` in target element.
+    ///
+    /// Text runs wrapped from mixed content are labelled by their parent element.
+    pub fn find_text(&self, element: &ElementRef, text: &str, font_sizes: &FontSizes) -> &str {
+        let text = text.trim();
+        self.labels
+            .iter()
+            .find(|label| label.matches(element, text, font_sizes))
+            .map(|label| label.title.as_str())
+            .unwrap_or("正文")
+    }
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self::new(vec![]).expect("default labels must be vaild")
+    }
+}
+
+fn default_labels() -> Vec<RawLabel> {
+    let label =
+        |selector: Option<&str>, pattern: Option<&str>, size: Option<f32>, title: &str| RawLabel {
+            selector: selector.map(String::from),
+            pattern: pattern.map(String::from),
+            min_font_size: size,
+            title: title.to_string(),
+        };
+    vec![
+        label(
+            None,
+            Some(
+                r"^(\d{4}\s*[-/.年]\s*\d{1,2}(\s*[-/.月]\s*\d{1,2}\s*日?)?|\d{1,2}\s*月\s*\d{1,2}\s*日)$",
+            ),
+            None,
+            "日期",
+        ),
+        label(
+            None,
+            Some(
+                r"^(电话|手机|TEL|Tel|tel)?[:：]?\s*((\+?86[-\s]?)?1\d{2}[-\s]?\d{4}[-\s]?\d{4}|0\d{2,3}-?\d{7,8}|400-?\d{3}-?\d{4})$",
+            ),
+            None,
+            "电话",
+        ),
+        label(
+            None,
+            Some(r"^[¥￥$]?\s*[+-]?\d[\d,]*(\.\d+)?\s*(%|元|万|亿)?$"),
+            None,
+            "数字",
+        ),
+        label(Some("h1"), None, None, "主标题"),
+        label(Some("h2, h3, h4, h5, h6"), None, None, "副标题"),
+        label(
+            Some("[class*=subtitle], [class*=sub-title]"),
+            None,
+            None,
+            "副标题",
+        ),
+        label(None, None, Some(36.0), "主标题"),
+        label(None, None, Some(24.0), "副标题"),
+        label(Some("[class*=title]"), None, None, "标题"),
+    ]
+}
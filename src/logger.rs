@@ -0,0 +1,52 @@
+use std::io::{self, IsTerminal};
+
+use anyhow::{anyhow, Result};
+use tracing::Level;
+use tracing_subscriber::{filter::Targets, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::{
+    args::{LogFormat, LogOptions},
+    progress::Progress,
+};
+
+/// Initial global logger, logs are written to stderr.
+///
+/// Default level is info, only warnings and lines of the whole run, result of each
+/// template is shown by progress. `-v` shows stage results and steps of every template,
+/// `-vv` shows every file, `-q` shows errors only.
+///
+/// - `options`: log options from command line
+/// - `progress`: progress bars that logs are written above
+pub fn init(options: &LogOptions, progress: &Progress) -> Result<()> {
+    let level = if options.quiet {
+        Level::ERROR
+    } else {
+        match options.verbose {
+            0 => Level::INFO,
+            1 => Level::DEBUG,
+            _ => Level::TRACE,
+        }
+    };
+    // only logs of eris, dependencies such as hyper log too
+    let targets = Targets::new().with_target(env!("CARGO_CRATE_NAME"), level);
+    let writer = progress.writer();
+    let layer = fmt::layer()
+        .with_writer(move || writer.clone())
+        .with_ansi(io::stderr().is_terminal());
+    let registry = tracing_subscriber::registry().with(targets);
+    match options.log_format {
+        LogFormat::Text => registry
+            .with(layer.with_target(false).without_time())
+            .try_init(),
+        LogFormat::Json => registry
+            .with(
+                layer
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(true)
+                    .with_span_list(false),
+            )
+            .try_init(),
+    }
+    .map_err(|err| anyhow!("cannot initial logger {}", err))
+}
@@ -3,26 +3,36 @@ use anyhow::Result;
 use clap::Parser;
 use sisyphus::Sisyphus;
 
-use crate::args::Args;
+use crate::{args::Args, progress::Progress};
 
 mod args;
+mod assets;
+mod check;
 mod consts;
+mod css;
+mod dom;
 mod errors;
 mod http;
+mod label;
+mod logger;
+mod manifest;
+mod outcome;
+mod progress;
+mod report;
+mod rules;
+mod sanitize;
 mod sisyphus;
+mod styles;
+mod thumb;
+mod utils;
 mod ziper;
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    let progress = Progress::new(args.log.quiet);
+    logger::init(&args.log, &progress)?;
 
-    let sisyphus = Sisyphus::new(
-        args.mode,
-        &args.directory,
-        &args.output,
-        &args.base_url,
-        &args.token,
-        &args.upload_name,
-    )?;
+    let sisyphus = Sisyphus::new(&args, &progress)?;
     sisyphus.process()?;
     Ok(())
 }
@@ -0,0 +1,76 @@
+use anyhow::{anyhow, Result};
+use ego_tree::NodeId;
+use scraper::{ElementRef, Html};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::template_size;
+
+/// Editable fields of a formatted template, written to `template.json`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Template name, such as `A002_GG42_1100X600`
+    pub name: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fields: Vec<Field>,
+}
+
+/// An annotated element.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Field {
+    /// `data-id` value, stable id of the element
+    pub id: String,
+    /// `data-template` value, such as `text` or `img`
+    pub kind: String,
+    /// `data-title` value
+    pub title: String,
+    /// Default text of text fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Default image of image fields
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub src: Option<String>,
+}
+
+impl Manifest {
+    /// Collect all annotated elements under `body`.
+    ///
+    /// - `name`: template name
+    /// - `doc`: annotated document
+    /// - `body`: body element node id
+    pub fn new(name: &str, doc: &Html, body: NodeId) -> Result<Self> {
+        let body = doc
+            .tree
+            .get(body)
+            .and_then(ElementRef::wrap)
+            .ok_or(anyhow!("cannot find body element"))?;
+        let fields = body
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .filter_map(|element| {
+                let value = element.value();
+                let kind = value.attr("data-template")?;
+                let id = value.attr("data-id")?;
+                let text = (kind == "text").then(|| {
+                    let text = element.text().collect::<String>();
+                    text.trim().to_string()
+                });
+                let src = value.attr("src").or(value.attr("data-src"));
+                Some(Field {
+                    id: id.to_string(),
+                    kind: kind.to_string(),
+                    title: value.attr("data-title").unwrap_or_default().to_string(),
+                    text,
+                    src: src.map(String::from),
+                })
+            })
+            .collect();
+        let (width, height) = template_size(name).unzip();
+        Ok(Self {
+            name: name.to_string(),
+            width,
+            height,
+            fields,
+        })
+    }
+}
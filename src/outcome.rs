@@ -0,0 +1,124 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Error;
+
+use crate::utils::template_size;
+
+/// Final status of a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Succeeded,
+    /// Left untouched, with the reason
+    Skipped(String),
+    /// Error chain, the outermost context first
+    Failed(Vec<String>),
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Succeeded => "succeeded",
+            Status::Skipped(_) => "skipped",
+            Status::Failed(_) => "failed",
+        }
+    }
+}
+
+/// Result of processing a single template.
+#[derive(Debug)]
+pub struct Outcome {
+    /// Target zip or folder
+    pub path: PathBuf,
+    pub status: Status,
+    /// The last stage template reached, such as `format`
+    pub stage: &'static str,
+    pub duration: Duration,
+    /// Files written for the template
+    pub outputs: Vec<PathBuf>,
+    pub upload_name: Option<String>,
+}
+
+impl Outcome {
+    /// Outcome of a template that starts at `stage`.
+    pub fn new(path: &Path, stage: &'static str) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            status: Status::Succeeded,
+            stage,
+            duration: Duration::ZERO,
+            outputs: vec![],
+            upload_name: None,
+        }
+    }
+
+    /// Record result and time of the template.
+    pub fn finish(&mut self, result: Result<Status, Error>, duration: Duration) {
+        self.status = match result {
+            Ok(status) => status,
+            Err(err) => Status::Failed(err.chain().map(|e| e.to_string()).collect()),
+        };
+        self.duration = duration;
+    }
+
+    /// Template size parsed from name.
+    pub fn size(&self) -> Option<(u32, u32)> {
+        template_size(&self.name())
+    }
+
+    /// Name of target zip or folder.
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.to_string_lossy().to_string())
+    }
+}
+
+/// Print summary table of all templates.
+///
+/// Return the number of failed templates.
+pub fn summary(outcomes: &[Outcome]) -> usize {
+    let width = outcomes
+        .iter()
+        .map(|o| o.name().chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("Template".len());
+    let (mut succeeded, mut skipped, mut failed) = (0, 0, 0);
+
+    println!("Summary:");
+    println!("  {:<9}  {:<width$}  Message", "Status", "Template");
+    for outcome in outcomes {
+        let name = outcome.name();
+        match &outcome.status {
+            Status::Succeeded => {
+                succeeded += 1;
+                println!("  {:<9}  {}", "succeeded", name);
+            }
+            Status::Skipped(reason) => {
+                skipped += 1;
+                println!("  {:<9}  {:<width$}  {}", "skipped", name, reason);
+            }
+            Status::Failed(chain) => {
+                failed += 1;
+                let mut lines = chain.iter().flat_map(|e| e.lines());
+                let first = lines.next().unwrap_or_default();
+                println!("  {:<9}  {:<width$}  {}", "failed", name, first);
+                for line in lines {
+                    println!("  {:<9}  {:<width$}    {}", "", "", line.trim_start());
+                }
+            }
+        }
+    }
+    println!(
+        "{} template(s): {} succeeded, {} skipped, {} failed",
+        outcomes.len(),
+        succeeded,
+        skipped,
+        failed
+    );
+    failed
+}
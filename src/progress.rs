@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    io::{self, IsTerminal, Write},
+    sync::Mutex,
+    time::Duration,
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::outcome::{Outcome, Status};
+
+const OVERALL_TEMPLATE: &str = "{prefix:>8} [{bar:30}] {pos}/{len} {elapsed_precise}";
+const STAGE_TEMPLATE: &str = "{spinner} {prefix}: {msg}";
+const UPLOAD_TEMPLATE: &str = "{spinner} {prefix}: upload [{bar:20}] {bytes}/{total_bytes}";
+
+/// Progress of batch processing.
+///
+/// Draw one overall bar and one status line for each worker when stdout is a terminal,
+/// otherwise print a plain line when each template finished, unless quiet.
+#[derive(Debug)]
+pub struct Progress {
+    multi: MultiProgress,
+    /// Draw progress bars, otherwise print plain lines
+    enabled: bool,
+    /// Do not print plain lines, `-q`
+    quiet: bool,
+    overall: ProgressBar,
+    /// Status line of each rayon worker, by thread index
+    workers: Mutex<HashMap<usize, ProgressBar>>,
+}
+
+impl Progress {
+    /// - `quiet`: skip plain lines when stdout is not a terminal
+    pub fn new(quiet: bool) -> Self {
+        let enabled = io::stdout().is_terminal();
+        let target = if enabled {
+            ProgressDrawTarget::stdout()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        Self {
+            multi: MultiProgress::with_draw_target(target),
+            enabled,
+            quiet,
+            overall: ProgressBar::hidden(),
+            workers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Writer that hides progress bars while writing to stderr,
+    /// so log lines never break the bars.
+    pub fn writer(&self) -> Suspended {
+        Suspended(self.multi.clone())
+    }
+
+    /// Start the overall bar of mode.
+    ///
+    /// - `mode`: mode name
+    /// - `len`: count of templates
+    pub fn start(&self, mode: &str, len: usize) {
+        let style = ProgressStyle::with_template(OVERALL_TEMPLATE)
+            .expect("template must be vaild")
+            .progress_chars("=> ");
+        self.overall.set_style(style);
+        self.overall.set_prefix(mode.to_string());
+        self.overall.set_length(len as u64);
+        self.multi.add(self.overall.clone()).tick();
+    }
+
+    /// Status line of current rayon worker, created at first use.
+    fn worker(&self) -> ProgressBar {
+        let index = rayon::current_thread_index().unwrap_or_default();
+        let mut workers = self.workers.lock().unwrap_or_else(|err| err.into_inner());
+        workers
+            .entry(index)
+            .or_insert_with(|| {
+                let bar = self.multi.add(ProgressBar::new_spinner());
+                bar.enable_steady_tick(Duration::from_millis(120));
+                bar
+            })
+            .clone()
+    }
+
+    /// Show the stage that current worker is running.
+    ///
+    /// - `name`: template name
+    /// - `stage`: stage name, such as `format`
+    pub fn stage(&self, name: &str, stage: &str) {
+        let bar = self.worker();
+        bar.set_style(
+            ProgressStyle::with_template(STAGE_TEMPLATE).expect("template must be vaild"),
+        );
+        bar.set_prefix(name.to_string());
+        bar.set_message(stage.to_string());
+    }
+
+    /// Switch current worker to upload, return the bar that counts bytes sent.
+    pub fn upload(&self) -> ProgressBar {
+        let bar = self.worker();
+        let style = ProgressStyle::with_template(UPLOAD_TEMPLATE)
+            .expect("template must be vaild")
+            .progress_chars("=> ");
+        bar.set_style(style);
+        bar.set_position(0);
+        bar
+    }
+
+    /// Count finished template.
+    pub fn done(&self, outcome: &Outcome) {
+        if self.enabled || self.quiet {
+            self.overall.inc(1);
+            return;
+        }
+        // keep counter and line in order between workers
+        let _workers = self.workers.lock().unwrap_or_else(|err| err.into_inner());
+        self.overall.inc(1);
+        let status = match &outcome.status {
+            Status::Succeeded => outcome.status.name().to_string(),
+            Status::Skipped(reason) => format!("skipped, {}", reason),
+            // root cause only, outer contexts repeat the stage
+            Status::Failed(errors) => {
+                format!(
+                    "failed, {}",
+                    one_line(errors.last().map_or("", String::as_str))
+                )
+            }
+        };
+        println!(
+            "[{}/{}] {} {} {}",
+            self.overall.position(),
+            self.overall.length().unwrap_or_default(),
+            outcome.name(),
+            outcome.stage,
+            status
+        );
+    }
+
+    /// Remove worker lines, keep the overall bar.
+    pub fn finish(&self) {
+        let mut workers = self.workers.lock().unwrap_or_else(|err| err.into_inner());
+        for (_, bar) in workers.drain() {
+            bar.finish_and_clear();
+        }
+        self.overall.finish();
+    }
+}
+
+/// Collapse multi-line error onto one line, list items are joined with `; `.
+///
+/// `"2 problem(s):\n- a\n- b"` => `"2 problem(s): a; b"`
+fn one_line(error: &str) -> String {
+    let mut lines = error
+        .lines()
+        .map(|line| line.trim().trim_start_matches("- ").trim())
+        .filter(|line| !line.is_empty());
+    let first = lines.next().unwrap_or_default();
+    let rest = lines.collect::<Vec<_>>().join("; ");
+    match (rest.is_empty(), first.ends_with(':')) {
+        (true, _) => first.to_string(),
+        (false, true) => format!("{} {}", first, rest),
+        (false, false) => format!("{}; {}", first, rest),
+    }
+}
+
+/// Stderr writer that suspends progress bars.
+#[derive(Debug, Clone)]
+pub struct Suspended(MultiProgress);
+
+impl Write for Suspended {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.suspend(|| io::stderr().write(buf))
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.suspend(|| io::stderr().write_all(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_on_one_line() {
+        assert_eq!(one_line("cannot read zip"), "cannot read zip");
+        assert_eq!(
+            one_line("2 problem(s):\n- missing index.html\n- missing thumbnail a.jpg"),
+            "2 problem(s): missing index.html; missing thumbnail a.jpg"
+        );
+        assert_eq!(
+            one_line("invalid annotations:\n  - data-id \"a\" is duplicated"),
+            "invalid annotations: data-id \"a\" is duplicated"
+        );
+    }
+}
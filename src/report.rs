@@ -0,0 +1,309 @@
+use std::{fs, path::Path, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+
+use crate::{
+    args::{Mode, ReportFormat},
+    outcome::{Outcome, Status},
+};
+
+/// Run report written by `--report`.
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    mode: &'static str,
+    duration_ms: u128,
+    total: usize,
+    succeeded: usize,
+    skipped: usize,
+    failed: usize,
+    templates: Vec<Record<'a>>,
+}
+
+/// Result of a single template in report.
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    template: String,
+    path: String,
+    /// The last stage template reached
+    stage: &'static str,
+    status: &'static str,
+    duration_ms: u128,
+    outputs: Vec<String>,
+    upload_name: Option<&'a str>,
+    width: Option<u32>,
+    height: Option<u32>,
+    /// Reason of skipped template
+    skip_reason: Option<&'a str>,
+    /// Error chain of failed template, joined with `: `
+    error: Option<String>,
+}
+
+impl<'a> From<&'a Outcome> for Record<'a> {
+    fn from(outcome: &'a Outcome) -> Self {
+        let (width, height) = outcome.size().unzip();
+        Self {
+            template: outcome.name(),
+            path: outcome.path.to_string_lossy().to_string(),
+            stage: outcome.stage,
+            status: outcome.status.name(),
+            duration_ms: outcome.duration.as_millis(),
+            outputs: outcome
+                .outputs
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            upload_name: outcome.upload_name.as_deref(),
+            width,
+            height,
+            skip_reason: match &outcome.status {
+                Status::Skipped(reason) => Some(reason),
+                _ => None,
+            },
+            error: match &outcome.status {
+                Status::Failed(chain) => Some(chain.join(": ")),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Write result of every template to report file.
+///
+/// - `path`: report file
+/// - `format`: report format, detected from file extension when not specified
+/// - `mode`: eris mode
+/// - `outcomes`: result of every template
+/// - `duration`: time of the whole run
+pub fn write(
+    path: &Path,
+    format: Option<ReportFormat>,
+    mode: Mode,
+    outcomes: &[Outcome],
+    duration: Duration,
+) -> Result<()> {
+    let format = format.unwrap_or_else(|| {
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("csv") => ReportFormat::Csv,
+            Some("xml") => ReportFormat::Junit,
+            _ => ReportFormat::Json,
+        }
+    });
+    let content = render(format, mode, outcomes, duration)?;
+    fs::write(path, content).with_context(|| anyhow!("cannot write report {:?}", path))
+}
+
+/// Render report content in `format`.
+fn render(
+    format: ReportFormat,
+    mode: Mode,
+    outcomes: &[Outcome],
+    duration: Duration,
+) -> Result<String> {
+    let count = |name: &str| outcomes.iter().filter(|o| o.status.name() == name).count();
+    let report = Report {
+        mode: mode.name(),
+        duration_ms: duration.as_millis(),
+        total: outcomes.len(),
+        succeeded: count("succeeded"),
+        skipped: count("skipped"),
+        failed: count("failed"),
+        templates: outcomes.iter().map(Record::from).collect(),
+    };
+    Ok(match format {
+        ReportFormat::Json => serde_json::to_string_pretty(&report)?,
+        ReportFormat::Csv => csv(&report),
+        ReportFormat::Junit => junit(&report),
+    })
+}
+
+/// One line for every template.
+fn csv(report: &Report) -> String {
+    let mut lines = vec![[
+        "template",
+        "path",
+        "mode",
+        "stage",
+        "status",
+        "duration_ms",
+        "outputs",
+        "upload_name",
+        "width",
+        "height",
+        "skip_reason",
+        "error",
+    ]
+    .join(",")];
+    for record in &report.templates {
+        let fields = [
+            record.template.clone(),
+            record.path.clone(),
+            report.mode.to_string(),
+            record.stage.to_string(),
+            record.status.to_string(),
+            record.duration_ms.to_string(),
+            record.outputs.join(";"),
+            record.upload_name.unwrap_or_default().to_string(),
+            record.width.map(|w| w.to_string()).unwrap_or_default(),
+            record.height.map(|h| h.to_string()).unwrap_or_default(),
+            record.skip_reason.unwrap_or_default().to_string(),
+            record.error.clone().unwrap_or_default(),
+        ];
+        let fields = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>();
+        lines.push(fields.join(","));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Quote CSV field that contains separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A test suite of current mode, a test case for every template.
+fn junit(report: &Report) -> String {
+    let seconds = |ms: u128| format!("{:.3}", ms as f64 / 1000.0);
+    let suite = format!(
+        "tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\"",
+        report.total,
+        report.failed,
+        report.skipped,
+        seconds(report.duration_ms)
+    );
+    let mut xml = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        format!("<testsuites name=\"eris\" {}>", suite),
+        format!("  <testsuite name=\"eris {}\" {}>", report.mode, suite),
+    ];
+    for record in &report.templates {
+        xml.push(format!(
+            "    <testcase classname=\"eris.{}\" name=\"{}\" time=\"{}\">",
+            report.mode,
+            xml_escape(&record.template),
+            seconds(record.duration_ms)
+        ));
+        if let Some(reason) = record.skip_reason {
+            xml.push(format!(
+                "      <skipped message=\"{}\"/>",
+                xml_escape(reason)
+            ));
+        }
+        if let Some(error) = &record.error {
+            xml.push(format!(
+                "      <failure message=\"{} failed\" type=\"{}\">{}</failure>",
+                record.stage,
+                record.stage,
+                xml_escape(error)
+            ));
+        }
+        let mut out = vec![format!("stage: {}", record.stage)];
+        if let (Some(width), Some(height)) = (record.width, record.height) {
+            out.push(format!("size: {}x{}", width, height));
+        }
+        if let Some(name) = record.upload_name {
+            out.push(format!("upload name: {}", name));
+        }
+        out.extend(record.outputs.iter().map(|o| format!("output: {}", o)));
+        xml.push(format!(
+            "      <system-out>{}</system-out>",
+            xml_escape(&out.join("\n"))
+        ));
+        xml.push("    </testcase>".to_string());
+    }
+    xml.push("  </testsuite>".to_string());
+    xml.push("</testsuites>".to_string());
+    xml.join("\n") + "\n"
+}
+
+/// Escape text for both XML content and attribute values.
+///
+/// Line breaks are kept as character references, attribute values would
+/// normalize them to spaces. Other control characters are invalid in XML.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcomes() -> Vec<Outcome> {
+        let mut failed = Outcome::new(Path::new("t/A002_GG42_1100X600.zip"), "format");
+        let err = anyhow!("bad <p> & \"quote\",\nnext line").context("format failed");
+        failed.finish(Err(err), Duration::from_millis(1500));
+        let mut skipped = Outcome::new(Path::new("t/B001_GG1_800X400.zip"), "format");
+        skipped.finish(
+            Ok(Status::Skipped("already formatted".to_string())),
+            Duration::ZERO,
+        );
+        vec![failed, skipped]
+    }
+
+    fn render(format: ReportFormat) -> String {
+        super::render(format, Mode::Format, &outcomes(), Duration::from_secs(2)).unwrap()
+    }
+
+    #[test]
+    fn json_keeps_error() {
+        let report =
+            serde_json::from_str::<serde_json::Value>(&render(ReportFormat::Json)).unwrap();
+        assert_eq!(report["failed"], 1);
+        assert_eq!(report["skipped"], 1);
+        assert_eq!(
+            report["templates"][0]["error"],
+            "format failed: bad <p> & \"quote\",\nnext line"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_fields() {
+        let csv = render(ReportFormat::Csv);
+        let lines = csv.split_inclusive('\n').collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[1..3].concat(),
+            "A002_GG42_1100X600.zip,t/A002_GG42_1100X600.zip,format,format,failed,1500,,,1100,600,,\
+             \"format failed: bad <p> & \"\"quote\"\",\nnext line\"\n"
+        );
+        assert_eq!(
+            lines[3],
+            "B001_GG1_800X400.zip,t/B001_GG1_800X400.zip,format,format,skipped,0,,,800,400,already formatted,\n"
+        );
+    }
+
+    #[test]
+    fn junit_escapes_error() {
+        let xml = render(ReportFormat::Junit);
+        assert!(xml.contains(
+            r#"<testsuite name="eris format" tests="2" failures="1" skipped="1" time="2.000">"#
+        ));
+        assert!(xml.contains(
+            "<failure message=\"format failed\" type=\"format\">\
+             format failed: bad &lt;p&gt; &amp; &quot;quote&quot;,&#10;next line</failure>"
+        ));
+        assert!(xml.contains(r#"<skipped message="already formatted"/>"#));
+        assert_eq!(xml_escape("a\u{1}b\tc"), "ab&#9;c");
+    }
+}
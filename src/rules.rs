@@ -0,0 +1,270 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use scraper::{ElementRef, Selector};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    consts::RULES_FILES,
+    dom::{vaild_text, Backgrounds},
+    label::{Labels, RawLabel},
+    sanitize::Allowlist,
+};
+
+/// `data-template` values that are always allowed.
+const DEFAULT_KINDS: [&str; 3] = ["text", "img", "bg"];
+
+/// What to do with elements matched by a rule.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Add rule attributes to the element.
+    #[default]
+    Annotate,
+    /// Leave the element untouched.
+    Ignore,
+}
+
+/// A single annotation rule.
+///
+/// ```toml
+/// [[rule]]
+/// selector = "img"
+/// priority = 0
+/// action = "annotate"
+/// attrs = { data-template = "img", data-title = "图片" }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawRule {
+    /// CSS selector
+    pub selector: String,
+    /// Only match elements that directly contain vaild text
+    #[serde(default)]
+    pub text: bool,
+    /// Only match elements that have a background image,
+    /// the image path will be add as `data-src`
+    #[serde(default)]
+    pub background: bool,
+    /// Rules with higher priority win, the later rule wins on tie
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub action: Action,
+    /// Attributes add to the matched element
+    #[serde(default)]
+    pub attrs: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRules {
+    #[serde(default, rename = "rule", alias = "rules")]
+    rules: Vec<RawRule>,
+    #[serde(default, rename = "label", alias = "labels")]
+    labels: Vec<RawLabel>,
+    #[serde(default)]
+    sanitize: Allowlist,
+    /// Extra `data-template` values that designer annotations may use
+    #[serde(default)]
+    kinds: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub selector: Selector,
+    pub text: bool,
+    pub background: bool,
+    pub priority: i32,
+    pub action: Action,
+    pub attrs: BTreeMap<String, String>,
+}
+
+impl TryFrom<RawRule> for Rule {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawRule) -> Result<Self> {
+        let selector = Selector::parse(&raw.selector)
+            .map_err(|err| anyhow!("cannot create selector {} {}", raw.selector, err))?;
+        Ok(Self {
+            selector,
+            text: raw.text,
+            background: raw.background,
+            priority: raw.priority,
+            action: raw.action,
+            attrs: raw.attrs,
+        })
+    }
+}
+
+impl Rule {
+    /// Detect target element is matched by this rule.
+    ///
+    /// - `element`: target element
+    /// - `backgrounds`: background images of elements
+    pub fn matches(&self, element: &ElementRef, backgrounds: &Backgrounds) -> bool {
+        if !self.selector.matches(element) {
+            return false;
+        }
+        if self.background && !backgrounds.contains_key(&element.id()) {
+            return false;
+        }
+        !self.text || has_text(element)
+    }
+}
+
+/// Annotation rules for format mode.
+#[derive(Debug)]
+pub struct Rules {
+    rules: Vec<Rule>,
+    /// `data-title` labels for text elements that rules not specified
+    pub labels: Labels,
+    /// Active content that is kept when sanitizing
+    pub allowlist: Allowlist,
+    /// Allowed `data-template` values of designer annotations
+    pub kinds: Vec<String>,
+}
+
+impl Rules {
+    /// Load rules.
+    ///
+    /// - `path`: user specified rules file.
+    /// - `directory`: target directory, `eris.rules.toml` or `eris.rules.json`
+    ///   in it will be used when `path` is not specified.
+    ///
+    /// Fallback to default rules when no rules file found.
+    pub fn load(path: Option<&Path>, directory: &Path) -> Result<Self> {
+        let path = match path {
+            Some(p) => Some(PathBuf::from(p)),
+            None => RULES_FILES
+                .iter()
+                .map(|name| directory.join(name))
+                .find(|p| p.exists()),
+        };
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+
+        info!("using rules file {:?}", path);
+        let content = fs::read_to_string(&path)
+            .with_context(|| anyhow!("cannot read rules file {:?}", path))?;
+        let raw = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str::<RawRules>(&content)
+                .with_context(|| anyhow!("cannot parse rules file {:?}", path))?
+        } else {
+            toml::from_str::<RawRules>(&content)
+                .with_context(|| anyhow!("cannot parse rules file {:?}", path))?
+        };
+        let mut rules = Self::try_from(raw.rules)?;
+        rules.labels = Labels::new(raw.labels)?;
+        rules.allowlist = raw.sanitize;
+        for kind in raw.kinds {
+            if !rules.kinds.contains(&kind) {
+                rules.kinds.push(kind);
+            }
+        }
+        Ok(rules)
+    }
+
+    /// Find the rule that applies to target element.
+    pub fn find(&self, element: &ElementRef, backgrounds: &Backgrounds) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(element, backgrounds))
+            .max_by_key(|rule| rule.priority)
+    }
+
+    /// Find the text rule that applies to text of target element.
+    ///
+    /// Used for element that annotated by other rule but directly contains text,
+    /// such as text on a background image.
+    pub fn find_text(&self, element: &ElementRef, backgrounds: &Backgrounds) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.text && rule.action == Action::Annotate)
+            .filter(|rule| rule.matches(element, backgrounds))
+            .max_by_key(|rule| rule.priority)
+    }
+}
+
+impl TryFrom<Vec<RawRule>> for Rules {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: Vec<RawRule>) -> Result<Self> {
+        let rules = raw
+            .into_iter()
+            .map(Rule::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        // kinds of default rules and kinds that rules add
+        let mut kinds = DEFAULT_KINDS.map(String::from).to_vec();
+        for kind in rules
+            .iter()
+            .filter_map(|rule| rule.attrs.get("data-template"))
+        {
+            if !kinds.contains(kind) {
+                kinds.push(kind.clone());
+            }
+        }
+        Ok(Self {
+            rules,
+            labels: Labels::default(),
+            allowlist: Allowlist::default(),
+            kinds,
+        })
+    }
+}
+
+impl Default for Rules {
+    /// Annotate every element that has a background image, every `<img>`
+    /// and every element that contains text, text titles are labelled by
+    /// default labels.
+    ///
+    /// Background wins over text, text of background element is wrapped and
+    /// annotated separately.
+    fn default() -> Self {
+        let attrs = |template: &str, title: Option<&str>| {
+            let mut attrs = BTreeMap::from([("data-template".to_string(), template.to_string())]);
+            if let Some(title) = title {
+                attrs.insert("data-title".to_string(), title.to_string());
+            }
+            attrs
+        };
+        let raw = vec![
+            RawRule {
+                selector: "*".to_string(),
+                text: false,
+                background: true,
+                priority: 1,
+                action: Action::Annotate,
+                attrs: attrs("bg", Some("背景")),
+            },
+            RawRule {
+                selector: "*".to_string(),
+                text: true,
+                background: false,
+                priority: 0,
+                action: Action::Annotate,
+                attrs: attrs("text", None),
+            },
+            RawRule {
+                selector: "img".to_string(),
+                text: false,
+                background: false,
+                priority: 0,
+                action: Action::Annotate,
+                attrs: attrs("img", Some("图片")),
+            },
+        ];
+        Self::try_from(raw).expect("default rules must be vaild")
+    }
+}
+
+/// Detect target element directly contains vaild text.
+fn has_text(element: &ElementRef) -> bool {
+    element
+        .children()
+        .any(|child| child.value().as_text().is_some_and(|text| vaild_text(text)))
+}
@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use ego_tree::NodeId;
+use scraper::{ElementRef, Html, Node};
+use serde::Deserialize;
+
+use crate::dom::describe;
+
+/// Elements that run code or embed other documents.
+const ACTIVE_ELEMENTS: [&str; 7] = [
+    "script", "iframe", "frame", "frameset", "object", "embed", "applet",
+];
+/// URL schemes that run code.
+const ACTIVE_SCHEMES: [&str; 2] = ["javascript:", "vbscript:"];
+/// Attributes that browsers load or navigate to as url, others are plain text.
+const URL_ATTRS: [&str; 9] = [
+    "href",
+    "src",
+    "action",
+    "formaction",
+    "xlink:href",
+    "poster",
+    "data",
+    "background",
+    "cite",
+];
+
+/// Active content that is allowed to stay in templates.
+///
+/// ```toml
+/// [sanitize]
+/// elements = ["iframe"]
+/// attributes = ["onclick"]
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Allowlist {
+    /// Active elements to keep, such as `iframe`
+    #[serde(default)]
+    pub elements: Vec<String>,
+    /// Event handler attributes to keep, such as `onclick`
+    #[serde(default)]
+    pub attributes: Vec<String>,
+}
+
+/// Active content found in template.
+#[derive(Debug)]
+pub struct Removal {
+    node: NodeId,
+    /// Attribute to remove, the whole element is removed when it's `None`
+    attr: Option<String>,
+    /// Human readable description
+    pub description: String,
+}
+
+impl Allowlist {
+    /// Find all active content under `body` that is not allowed.
+    ///
+    /// - `doc`: parsed document
+    /// - `body`: body element node id
+    pub fn find(&self, doc: &Html, body: NodeId) -> Result<Vec<Removal>> {
+        let body = doc
+            .tree
+            .get(body)
+            .and_then(ElementRef::wrap)
+            .ok_or(anyhow!("cannot find body element"))?;
+        let elements = self.elements.iter().map(|e| e.to_ascii_lowercase());
+        let elements = elements.collect::<HashSet<_>>();
+        let attributes = self.attributes.iter().map(|a| a.to_ascii_lowercase());
+        let attributes = attributes.collect::<HashSet<_>>();
+
+        let mut removals = vec![];
+        let mut removed = HashSet::new();
+        for element in body.descendants().filter_map(ElementRef::wrap) {
+            // children are removed with the element
+            if element.ancestors().any(|a| removed.contains(&a.id())) {
+                continue;
+            }
+            let value = element.value();
+            let name = value.name();
+            if ACTIVE_ELEMENTS.contains(&name) && !elements.contains(name) {
+                removed.insert(element.id());
+                removals.push(Removal {
+                    node: element.id(),
+                    attr: None,
+                    description: describe(value),
+                });
+                continue;
+            }
+            for (attr, attr_value) in value.attrs() {
+                let attr = attr.to_ascii_lowercase();
+                let reason = if attr.starts_with("on") {
+                    if attributes.contains(&attr) {
+                        continue;
+                    }
+                    "event handler"
+                } else if URL_ATTRS.contains(&attr.as_str()) && active_url(attr_value) {
+                    "script url"
+                } else {
+                    continue;
+                };
+                removals.push(Removal {
+                    node: element.id(),
+                    description: format!("{} {} on {}", reason, attr, describe(value)),
+                    attr: Some(attr),
+                });
+            }
+        }
+        Ok(removals)
+    }
+}
+
+/// Remove active content from document.
+///
+/// - `doc`: parsed document
+/// - `removals`: active content found by [`Allowlist::find`]
+pub fn remove(doc: &mut Html, removals: &[Removal]) -> Result<()> {
+    for removal in removals {
+        let mut node = doc
+            .tree
+            .get_mut(removal.node)
+            .ok_or(anyhow!("cannot find node {:?}", removal.node))?;
+        let Some(attr) = &removal.attr else {
+            node.detach();
+            continue;
+        };
+        if let Node::Element(element) = node.value() {
+            element
+                .attrs
+                .retain(|name, _| !name.local.as_ref().eq_ignore_ascii_case(attr));
+        }
+    }
+    Ok(())
+}
+
+/// Detect url runs code, such as `javascript:alert(1)`.
+///
+/// Whitespace and control characters are ignored like browsers do.
+fn active_url(value: &str) -> bool {
+    let url = value
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .take(16)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    ACTIVE_SCHEMES.iter().any(|scheme| url.starts_with(scheme))
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::Selector;
+
+    use super::*;
+
+    /// Sanitize `<body>` and return its html.
+    fn sanitize(html: &str, allowlist: &Allowlist) -> (String, Vec<String>) {
+        let mut doc = Html::parse_document(html);
+        let selector = Selector::parse("body").unwrap();
+        let body = doc.select(&selector).next().unwrap().id();
+        let removals = allowlist.find(&doc, body).unwrap();
+        remove(&mut doc, &removals).unwrap();
+        let body = doc.select(&selector).next().unwrap().html();
+        let descriptions = removals.into_iter().map(|r| r.description).collect();
+        (body, descriptions)
+    }
+
+    #[test]
+    fn active_elements_are_removed_with_children() {
+        let (html, removed) = sanitize(
+            r#"<body><p>a</p><script>alert(1)</script><iframe src="x.html"><p>b</p></iframe><object><embed src="x.swf"></object></body>"#,
+            &Allowlist::default(),
+        );
+        assert_eq!(html, "<body><p>a</p></body>");
+        assert_eq!(removed.len(), 3, "{:?}", removed);
+    }
+
+    #[test]
+    fn event_handlers_are_removed() {
+        let (html, removed) = sanitize(
+            r#"<body><div onclick="a()" ONMOUSEOVER="b()" class="c">d</div></body>"#,
+            &Allowlist::default(),
+        );
+        assert_eq!(html, r#"<body><div class="c">d</div></body>"#);
+        assert_eq!(removed.len(), 2);
+    }
+
+    #[test]
+    fn allowlist_is_kept() {
+        let allowlist = Allowlist {
+            elements: vec!["IFRAME".to_string()],
+            attributes: vec!["onclick".to_string()],
+        };
+        let (html, removed) = sanitize(
+            r#"<body><iframe src="x.html"></iframe><div onclick="a()" onload="b()"></div><script></script></body>"#,
+            &allowlist,
+        );
+        assert_eq!(
+            html,
+            r#"<body><iframe src="x.html"></iframe><div onclick="a()"></div></body>"#
+        );
+        assert_eq!(removed.len(), 2);
+    }
+
+    #[test]
+    fn script_urls_are_removed() {
+        let (html, removed) = sanitize(
+            "<body><a href=\" java\tscript:alert(1)\">a</a><a href=\"\u{1}JavaScript:x\">b</a>\
+             <form action=\"vbscript:x\"></form><a href=\"https://a.com\">c</a></body>",
+            &Allowlist::default(),
+        );
+        assert_eq!(
+            html,
+            r#"<body><a>a</a><a>b</a><form></form><a href="https://a.com">c</a></body>"#
+        );
+        assert_eq!(removed.len(), 3);
+    }
+
+    #[test]
+    fn text_attributes_are_kept() {
+        let source = r#"<body><p data-title="javascript: 教程" title="javascript:x">a</p></body>"#;
+        let (html, removed) = sanitize(source, &Allowlist::default());
+        assert!(removed.is_empty(), "{:?}", removed);
+        assert_eq!(
+            html,
+            r#"<body><p data-title="javascript: 教程" title="javascript:x">a</p></body>"#
+        );
+    }
+}
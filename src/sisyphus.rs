@@ -1,24 +1,34 @@
 use anyhow::{anyhow, Context, Result};
-use ego_tree::NodeRef;
-use html5ever::{
-    tendril::{fmt::UTF8, Tendril},
-    LocalName, Namespace, QualName,
-};
 
-use scraper::{ElementRef, Html, Node, Selector};
+use scraper::{Html, Selector};
+use sha2::{Digest, Sha256};
 use std::{
+    collections::{BTreeMap, HashMap},
     fs::{self, DirEntry, File},
     io::{Read, Write},
     path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
 };
+use tracing::{debug, error, error_span, info, warn};
 use walkdir::WalkDir;
 
 use crate::{
-    args::Mode,
-    consts::{METHOD_STORED, RESET_CSS},
+    args::{Args, Existing, FormatOptions, Mode, ReportFormat, ResetOrder, Sanitize},
+    assets, check,
+    consts::{BACKUP_FOLDER, CHECKSUMS_FILE, GENERATED_FILES, METHOD_STORED, RESET_CSS},
+    css::Stylesheet,
+    dom,
     errors::{ErisError, ErisResult},
     http::Http,
-    ziper::Ziper,
+    manifest::Manifest,
+    outcome::{self, Outcome, Status},
+    progress::Progress,
+    report,
+    rules::Rules,
+    sanitize, styles, thumb,
+    utils::template_size,
+    ziper::{self, Ziper},
 };
 
 #[derive(Debug)]
@@ -37,6 +47,22 @@ pub struct Sisyphus<'a> {
     http: Option<Http<'a>>,
     // Upload name prefix
     upload_name: &'a Option<String>,
+    // Format options
+    format: &'a FormatOptions,
+    // Format annotation rules
+    rules: Rules,
+    // Reset stylesheet
+    reset: Option<Stylesheet>,
+    // Only print the plan
+    dry_run: bool,
+    // Stop at the first failed template
+    fail_fast: bool,
+    // Run report file
+    report: &'a Option<PathBuf>,
+    // Run report file format
+    report_format: Option<ReportFormat>,
+    // Batch progress bars
+    progress: &'a Progress,
 }
 
 impl<'a> Sisyphus<'a> {
@@ -44,35 +70,43 @@ impl<'a> Sisyphus<'a> {
     ///
     /// Create new Sisyphus struct.
     ///
-    /// - `directory`: target diretory. Sisyphus will read all zip file in this directory.
-    /// - `output`: compress file output directory.
-    pub fn new(
-        mode: Mode,
-        directory: &PathBuf,
-        output: &Option<PathBuf>,
-        // Upload
-        base_url: &'a Option<String>,
-        token: &'a Option<String>,
-        upload_name: &'a Option<String>,
-    ) -> Result<Self> {
+    /// - `args`: command line arguments. Sisyphus will read all zip file in
+    ///   target directory, and compress files to output directory.
+    /// - `progress`: batch progress bars
+    pub fn new(args: &'a Args, progress: &'a Progress) -> Result<Self> {
+        let Args {
+            mode,
+            directory,
+            output,
+            base_url,
+            token,
+            upload_name,
+            dry_run,
+            fail_fast,
+            report,
+            report_format,
+            log: _,
+            format,
+        } = args;
+        let (mode, dry_run, fail_fast) = (*mode, *dry_run, *fail_fast);
         use Mode::*;
 
         // Format
         // Collect file list.
         let folder = |mut prev: Vec<_>, path: std::result::Result<_, std::io::Error>| {
             let target = match path {
-                Ok(p) => match format_path(p, mode != Compress) {
+                Ok(p) => match format_path(p, !matches!(mode, Compress | Revert)) {
                     Ok(path) => path,
                     Err(err) => match err {
                         ErisError::Empty(_) => return prev,
                         ErisError::Other(err) => {
-                            eprintln!("{}", err);
+                            error!("{}", err);
                             return prev;
                         }
                     },
                 },
                 Err(err) => {
-                    eprintln!("Error: read path failed {}", err);
+                    error!("read path failed {}", err);
                     return prev;
                 }
             };
@@ -100,16 +134,29 @@ impl<'a> Sisyphus<'a> {
             target_paths.fold(vec![], folder)
         };
         if file_list.is_empty() {
-            println!("No zip file found in {:?}", directory);
+            warn!("no zip file found in {:?}", directory);
         } else {
-            let len = file_list.len();
-            println!("Found {} file(s): ", len);
-            file_list.iter().for_each(|f| println!("{:?}", f));
+            info!("found {} file(s)", file_list.len());
+            file_list.iter().for_each(|f| debug!("{:?}", f));
         }
-        println!();
+
+        let rules = if matches!(mode, Format | Check | All) {
+            Rules::load(format.rules.as_deref(), &input_path)?
+        } else {
+            Rules::default()
+        };
+        let reset = if !matches!(mode, Format | All) || format.no_reset {
+            None
+        } else if let Some(path) = &format.reset {
+            let reset = fs::read_to_string(path)
+                .with_context(|| anyhow!("cannot read reset stylesheet {:?}", path))?;
+            Some(Stylesheet::parse(&reset))
+        } else {
+            Some(Stylesheet::parse(RESET_CSS))
+        };
 
         // Upload
-        let http = if mode == Upload {
+        let http = if matches!(mode, Upload | All) {
             let token = token
                 .as_ref()
                 .ok_or(anyhow!("not specify upload token!"))?
@@ -127,6 +174,14 @@ impl<'a> Sisyphus<'a> {
             ziper: Ziper::new(),
             http,
             upload_name,
+            format,
+            rules,
+            reset,
+            dry_run,
+            fail_fast,
+            report,
+            report_format: *report_format,
+            progress,
         };
         Ok(s)
     }
@@ -140,46 +195,104 @@ impl<'a> Sisyphus<'a> {
             None => return Err(anyhow!("convert filename failed")),
         };
         // create same name folder
-        let name = format_name(&file_name);
-        let mut dir_path = PathBuf::from(&self.directory);
-        dir_path.push(name);
+        let dir_path = self.directory.join(format_name(&file_name));
         if dir_path.exists() {
             fs::remove_dir_all(&dir_path)?;
         }
         fs::create_dir_all(&dir_path)?;
-        println!("String unzip {:?}", path);
+        debug!("unzip {:?}", path);
         let dir_path = dir_path.to_string_lossy();
         let ziper = &self.ziper;
         ziper.unzip(Some(&dir_path), path)?;
         Ok(())
     }
 
-    // Copy image that it's has same name with target folder
-    fn image_process(&self, image_path: &Path, folder_prefix: &str) -> Result<()> {
-        println!("Found thumb {:?}", &image_path);
-        let mut target_path = PathBuf::from(&folder_prefix);
-        target_path.push("thumb.jpg");
-        fs::copy(image_path, &target_path)?;
-        println!("Copy thumb to {:?} done", &target_path);
-        Ok(())
+    // Convert image that it's has same name with target folder to thumb
+    fn image_process(
+        &self,
+        image_path: &Path,
+        folder: &Path,
+        size: Option<(u32, u32)>,
+    ) -> Result<()> {
+        debug!("found thumb {:?}", &image_path);
+        let target_path = folder.join("thumb.jpg");
+        thumb::normalize(image_path, size, self.format.thumb_width, &target_path)
     }
 
-    fn style_process(&self, img: &ElementRef, html: &mut String) {
-        let old_img = img.value();
-        let h = format!("{:?}", old_img);
-        println!("Processing img tag {}", h);
-
-        let mut new_img = old_img.clone();
-        add_attr(&mut new_img, "data-template", "img");
-        add_attr(&mut new_img, "data-title", "图片");
+    /// Join template stylesheet and reset stylesheet by reset order.
+    fn order_styles(&self, styles: String, reset: Option<Stylesheet>) -> String {
+        let styles = styles.trim_end();
+        let Some(reset) = reset else {
+            return styles.to_string();
+        };
+        let reset = reset.to_string();
+        let reset = reset.trim_end();
+        match self.format.reset_order {
+            ResetOrder::Before => format!("{}\n{}", reset, styles),
+            ResetOrder::After => format!("{}\n{}", styles, reset),
+        }
+    }
 
-        let new_h = format!("{:?}", new_img);
-        println!("Processed img tag {}", new_h);
-        *html = html.replace(&h, &new_h);
+    /// Decide whether target template should be formatted by existing policy.
+    ///
+    /// Return the reason when the template is already formatted and should be left untouched.
+    ///
+    /// - `file`: target zip
+    /// - `folder`: folder that zip unzip to
+    fn existing(&self, file: &Path, folder: &Path) -> Result<Option<String>> {
+        let entries = self
+            .ziper
+            .entries(file)
+            .with_context(|| anyhow!("cannot read zip {:?}", file))?;
+        let Some(formatted) = formatted(folder, &entries) else {
+            // unformatted folder that has been edited, only reformat replaces it
+            if self.format.existing != Existing::Reformat && diverged(folder, &entries) {
+                debug!("skip, folder differs from zip");
+                return Ok(Some(format!(
+                    "{:?} differs from zip, use --existing reformat to replace it",
+                    folder
+                )));
+            }
+            return Ok(None);
+        };
+        match self.format.existing {
+            Existing::Skip => {
+                debug!("skip, already formatted");
+                Ok(Some("already formatted".to_string()))
+            }
+            Existing::Check => {
+                let problems = match formatted {
+                    Formatted::Folder => check::check_formatted(&|p| fs::read(folder.join(p)).ok()),
+                    Formatted::Zip => check::check_formatted(&|p| entries.get(p).cloned()),
+                };
+                if problems.is_empty() {
+                    debug!("already formatted, check passed");
+                    return Ok(Some("already formatted, check passed".to_string()));
+                }
+                Err(anyhow!(
+                    "{:?} already formatted, {} problem(s):\n  - {}",
+                    file,
+                    problems.len(),
+                    problems.join("\n  - ")
+                ))
+            }
+            Existing::Reformat => match reformat_blocker(formatted, folder, &entries) {
+                Some(reason) => Err(anyhow!("cannot reformat {:?}, {}", file, reason)),
+                None => Ok(None),
+            },
+        }
     }
 
     /// Unzip all target zip files, and format target templates.
-    fn format_process(&self, file: &Path) -> Result<()> {
+    fn format_process(&self, file: &Path) -> Result<Status> {
+        let file_name = file
+            .file_name()
+            .ok_or(anyhow!("convert filename failed"))?
+            .to_string_lossy();
+        let folder = self.directory.join(format_name(&file_name));
+        if let Some(reason) = self.existing(file, &folder)? {
+            return Ok(Status::Skipped(reason));
+        }
         self.unzip(file)?;
 
         let file = file.to_string_lossy();
@@ -188,14 +301,24 @@ impl<'a> Sisyphus<'a> {
             .strip_suffix(".zip")
             .ok_or(anyhow!("strip filename failed"))?;
 
-        let image_path = {
-            let mut p = PathBuf::from(&folder_prefix);
-            p.set_extension("jpg");
-            p
-        };
-        if image_path.exists() {
-            self.image_process(&image_path, folder_prefix)
-                .with_context(|| format!("Copy thumb image {:?} failed", &image_path))?;
+        let folder = Path::new(folder_prefix);
+        let name = folder
+            .file_name()
+            .ok_or(anyhow!("cannot get folder filename"))?
+            .to_string_lossy();
+        let size = template_size(&name);
+        // keep files in zip that will be overwritten
+        for generated in GENERATED_FILES {
+            backup(folder, generated)?;
+        }
+        if let Some(image_path) = thumb::find_sibling(folder) {
+            self.image_process(&image_path, folder, size)
+                .with_context(|| format!("Convert thumb image {:?} failed", &image_path))?;
+        } else {
+            let thumb_path = folder.join("thumb.jpg");
+            if let Err(err) = thumb::generate(folder, size, self.format.thumb_width, &thumb_path) {
+                warn!("generate thumb for {:?} failed {:#}", folder, err);
+            }
         }
 
         // create index html
@@ -212,63 +335,148 @@ impl<'a> Sisyphus<'a> {
             .context(format!("Cannot open {:?}", index_path))?;
         let mut index = String::new();
         index_file.read_to_string(&mut index)?;
-        let doc = Html::parse_document(&index);
-        // let doc = self.parse_html(&index_path)?;
+        let mut doc = Html::parse_document(&index);
+        let styles = styles::collect(&doc, &|p| fs::read_to_string(folder.join(p)))?;
+        for warning in &styles.warnings {
+            warn!("{} in {:?}", warning, folder);
+        }
+        let mut stylesheet = styles.stylesheet;
+
         let body_selector =
             Selector::parse("body").map_err(|err| anyhow!("cannot create selector {}", err))?;
         let body = doc
             .select(&body_selector)
             .next()
-            .ok_or(anyhow!("select target {:?} failed", body_selector))?;
-        let mut html = body.html();
+            .ok_or(anyhow!("select target {:?} failed", body_selector))?
+            .id();
 
-        // add data attributes to images
-        let image_selector =
-            Selector::parse("img").map_err(|err| anyhow!("cannot create selector {}", err))?;
-        for img in body.select(&image_selector) {
-            self.style_process(&img, &mut html);
+        // remove scripts, event handlers and embedded documents
+        let removals = self.rules.allowlist.find(&doc, body)?;
+        if !removals.is_empty() {
+            match self.format.sanitize {
+                Sanitize::Reject => {
+                    let removals = removals
+                        .iter()
+                        .map(|r| r.description.as_str())
+                        .collect::<Vec<_>>();
+                    return Err(anyhow!(
+                        "{:?} contains active content:\n  - {}",
+                        folder,
+                        removals.join("\n  - ")
+                    ));
+                }
+                Sanitize::Strip => {
+                    for removal in &removals {
+                        warn!("removed {} in {:?}", removal.description, folder);
+                    }
+                    sanitize::remove(&mut doc, &removals)?;
+                }
+            }
         }
 
-        // add data attributes to texts
-        for child in body.children() {
-            traverse_node(&child, &mut html)
-                .map_err(|err| anyhow!("Error parse DOM failed {err}"))?;
+        // add data attributes to images, backgrounds and texts
+        dom::annotate(&mut doc, body, &self.rules, &stylesheet)
+            .with_context(|| anyhow!("cannot annotate {:?}", folder))?;
+        let name = format_name(
+            &index_path
+                .parent()
+                .and_then(|p| p.file_name())
+                .ok_or(anyhow!("cannot get folder filename"))?
+                .to_string_lossy(),
+        )
+        .to_string();
+
+        // check asset references
+        let references = assets::references(&doc, body, &stylesheet)?;
+        for asset in assets::missing(&references, |p| folder.join(p).is_file()) {
+            warn!("asset {} not found in {:?}", asset, folder);
+        }
+        // manifest keeps original asset paths for the editor
+        let manifest = Manifest::new(&name, &doc, body)?;
+        // embed small assets, rewrite the others to public base url
+        let base = self
+            .format
+            .asset_base
+            .as_ref()
+            .map(|base| base.replace("{name}", &name));
+        if self.format.embed_assets || base.is_some() {
+            assets::rewrite(&mut doc, body, &mut stylesheet, &|url| {
+                self.format
+                    .embed_assets
+                    .then(|| assets::data_uri(folder, url, self.format.embed_max_size))
+                    .flatten()
+                    .or_else(|| base.as_ref().map(|base| assets::join_base(base, url)))
+            })?;
         }
 
-        let style_path = {
-            let mut p = PathBuf::from(folder_prefix);
-            p.push("style.css");
-            p
+        // remove reset declarations that template already declares for the same selector,
+        // reset after template overrides them on purpose
+        let mut reset = self.reset.clone();
+        if let Some(reset) = reset.as_mut() {
+            if self.format.reset_order == ResetOrder::Before {
+                reset.remove_duplicates(&stylesheet);
+            }
+        }
+
+        let html = if self.format.scope {
+            // scope template styles under root element
+            let class = scope_class(&name);
+            let root = format!(".{}", class);
+            let mut styles = stylesheet;
+            styles.scope(&root);
+            if let Some(reset) = reset.as_mut() {
+                reset.scope(&root);
+            }
+            let styles = self.order_styles(styles.to_string(), reset);
+            dom::scope(&mut doc, body, &class, &format!("\n{}", styles))?;
+            dom::serialize(&doc, body)?
+        } else {
+            let html = dom::serialize(&doc, body)?;
+            let styles = self.order_styles(stylesheet.to_string(), reset);
+            format!("<style>\n{}\n</style>\n{}", styles, html)
         };
-        let style_file = fs::read_to_string(style_path)?;
-        let styles = format!("<style>\n{}\n{}\n</style>", style_file, RESET_CSS);
-        let html = format!("{}\n{}", styles, html);
 
         // create new template.html
         let mut new_name = PathBuf::from(&index_path);
         new_name.set_file_name("template.html");
-        let mut template = File::options().write(true).create(true).open(&new_name)?;
+        let mut template = File::options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&new_name)?;
         template
             .write_all(html.as_bytes())
             .with_context(|| anyhow!("cannot write to file {:?}", &template))?;
-        // delete index.html
-        fs::remove_file(&index_path)?;
-        println!("{} process done\n", file);
-        Ok(())
+
+        // create template.json
+        let manifest_path = index_path.with_file_name("template.json");
+        fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+            .with_context(|| anyhow!("cannot write to file {:?}", &manifest_path))?;
+        // keep original index.html
+        backup(folder, "index.html")?;
+        // detect edits before reformat
+        let checksums_path = folder.join(BACKUP_FOLDER).join(CHECKSUMS_FILE);
+        fs::write(
+            &checksums_path,
+            serde_json::to_string_pretty(&checksums(folder)?)?,
+        )
+        .with_context(|| anyhow!("cannot write to file {:?}", &checksums_path))?;
+        debug!("format done");
+        Ok(Status::Succeeded)
     }
 
     /// Traverse all formated directories, compress to zip files.
-    fn compress_process(&self, path: &Path) -> Result<()> {
-        let mut out_path = PathBuf::from(&self.output);
-        let path_name = path
-            .iter()
-            .last()
-            .ok_or(anyhow!("cannot get folder filename"))
-            .with_context(|| anyhow!("{:?}", path))?
-            .to_string_lossy();
+    ///
+    /// Return path of the zip file.
+    fn compress_process(&self, path: &Path) -> Result<PathBuf> {
+        let (path_name, out_path) = self.output_path(path)?;
         let filename = format!("{}.zip", &path_name);
-        out_path.push(&filename);
-        println!("Starting zip {:?}", out_path);
+        let src_path = self.directory.join(&path_name);
+        // walkdir errors are dropped, a missing folder would become an empty zip
+        if !src_path.is_dir() {
+            return Err(anyhow!("template folder {:?} not found", src_path));
+        }
+        debug!("start zip {:?}", out_path);
 
         let file = File::options()
             .write(true)
@@ -277,8 +485,6 @@ impl<'a> Sisyphus<'a> {
             .open(&out_path)
             .with_context(|| anyhow!("open target {:?} failed", out_path))?;
 
-        let mut src_path = PathBuf::from(&self.directory);
-        src_path.push(&*path_name);
         let walkdir = WalkDir::new(&src_path);
 
         let ziper = &self.ziper;
@@ -288,42 +494,405 @@ impl<'a> Sisyphus<'a> {
             file,
             METHOD_STORED.ok_or(anyhow!("cannot use stored compression method"))?,
         )?;
-        println!("{} compress done\n", filename);
+        debug!("compress {} done", filename);
 
-        Ok(())
+        Ok(out_path)
     }
 
-    pub fn process(&self) -> Result<()> {
-        use rayon::prelude::*;
+    /// Folder name and output zip path of formatted folder.
+    ///
+    /// `./A002_GG42_1100X600` => `("A002_GG42_1100X600", "./output/A002_GG42_1100X600.zip")`
+    fn output_path(&self, path: &Path) -> Result<(String, PathBuf)> {
+        let path_name = path
+            .iter()
+            .next_back()
+            .ok_or(anyhow!("cannot get folder filename"))
+            .with_context(|| anyhow!("{:?}", path))?
+            .to_string_lossy()
+            .to_string();
+        let out_path = self.output.join(format!("{}.zip", &path_name));
+        Ok((path_name, out_path))
+    }
+
+    /// Folder that target zip unzip to.
+    ///
+    /// `./A002_GG42_1100X600.zip` => `./A002_GG42_1100X600`
+    fn template_folder(&self, file: &Path) -> Result<PathBuf> {
+        let file_name = file
+            .file_name()
+            .ok_or(anyhow!("convert filename failed"))?
+            .to_string_lossy();
+        Ok(self.directory.join(format_name(&file_name)))
+    }
+
+    /// Format target zip, record generated files.
+    fn format_outcome(&self, file: &Path, outcome: &mut Outcome) -> Result<Status> {
+        let status = self.format_process(file)?;
+        if status == Status::Succeeded {
+            let folder = self.template_folder(file)?;
+            outcome.outputs.extend(
+                GENERATED_FILES
+                    .iter()
+                    .map(|name| folder.join(name))
+                    .filter(|path| path.is_file()),
+            );
+        }
+        Ok(status)
+    }
+
+    /// Upload target zip, record upload name.
+    fn upload_outcome(&self, path: &Path, outcome: &mut Outcome) -> Result<Status> {
+        let http = self
+            .http
+            .as_ref()
+            .ok_or(anyhow!("http client initial failed"))?;
+        let form = http.form(path, self.upload_name)?;
+        outcome.upload_name = Some(form.name.clone());
+        http.upload(path, form, &self.progress.upload())?;
+        Ok(Status::Succeeded)
+    }
+
+    /// Format, compress and upload target zip in sequence.
+    ///
+    /// A template that fails one stage does not go on to the next stage.
+    fn pipeline_process(&self, file: &Path, outcome: &mut Outcome) -> Result<Status> {
+        let status = self
+            .format_outcome(file, outcome)
+            .context("format failed")?;
+
+        let folder = self.template_folder(file)?;
+        if let Status::Skipped(reason) = status {
+            if !folder.join("template.html").is_file() {
+                return Ok(Status::Skipped(format!(
+                    "{}, no formatted folder to compress",
+                    reason
+                )));
+            }
+        }
+
+        outcome.stage = "compress";
+        self.progress.stage(&outcome.name(), outcome.stage);
+        let (_, out_path) = self.output_path(&folder)?;
+        if out_path.exists() {
+            fs::remove_file(&out_path)
+                .with_context(|| anyhow!("cannot remove old zip {:?}", out_path))?;
+        }
+        let zip = self.compress_process(&folder).context("compress failed")?;
+        outcome.outputs.push(zip.clone());
+
+        outcome.stage = "upload";
+        self.progress.stage(&outcome.name(), outcome.stage);
+        self.upload_outcome(&zip, outcome).context("upload failed")
+    }
+
+    /// Restore formatted folder to its pre-format state.
+    ///
+    /// Generated files are removed, original files are moved back from backup folder.
+    fn revert_process(&self, path: &Path) -> Result<Status> {
+        let backup = path.join(BACKUP_FOLDER);
+        if !backup.join("index.html").is_file() {
+            return Err(anyhow!(
+                "cannot revert {:?}, original index.html not found in {:?}",
+                path,
+                backup
+            ));
+        }
+        for generated in GENERATED_FILES {
+            let generated = path.join(generated);
+            if generated.is_file() {
+                fs::remove_file(&generated)
+                    .with_context(|| anyhow!("cannot remove {:?}", generated))?;
+                debug!("remove {:?}", generated);
+            }
+        }
+        let checksums_path = backup.join(CHECKSUMS_FILE);
+        if checksums_path.is_file() {
+            fs::remove_file(&checksums_path)
+                .with_context(|| anyhow!("cannot remove {:?}", checksums_path))?;
+        }
+        for entry in fs::read_dir(&backup)? {
+            let entry = entry?;
+            let target = path.join(entry.file_name());
+            fs::rename(entry.path(), &target)
+                .with_context(|| anyhow!("cannot restore {:?}", target))?;
+            debug!("restore {:?}", target);
+        }
+        fs::remove_dir(&backup)?;
+        debug!("revert done");
+        Ok(Status::Succeeded)
+    }
+
+    /// Print what revert mode would do to target folder.
+    fn revert_plan(&self, path: &Path) {
+        let backup = path.join(BACKUP_FOLDER);
+        println!("Would revert {:?}", path);
+        if !backup.join("index.html").is_file() {
+            println!("  fail, original index.html not found in {:?}", backup);
+            return;
+        }
+        GENERATED_FILES
+            .iter()
+            .map(|generated| path.join(generated))
+            .filter(|generated| generated.is_file())
+            .for_each(|generated| println!("  remove {:?}", generated));
+        fs::read_dir(&backup)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name() != CHECKSUMS_FILE)
+            .for_each(|e| println!("  restore {:?}", path.join(e.file_name())));
+        println!("  remove {:?}", backup);
+    }
+
+    /// Print what format mode would do to target zip.
+    ///
+    /// Return false when format would fail.
+    fn format_plan(&self, file: &Path) -> Result<bool> {
+        let file_name = file
+            .file_name()
+            .ok_or(anyhow!("convert filename failed"))?
+            .to_string_lossy();
+        let folder = self.directory.join(format_name(&file_name));
+        let entries = self
+            .ziper
+            .entries(file)
+            .with_context(|| anyhow!("cannot read zip {:?}", file))?;
+
+        println!("Would format {:?}", file);
+        if formatted(&folder, &entries).is_none()
+            && self.format.existing != Existing::Reformat
+            && diverged(&folder, &entries)
+        {
+            println!("  skip, {:?} differs from zip", folder);
+            return Ok(true);
+        }
+        if let Some(formatted) = formatted(&folder, &entries) {
+            match self.format.existing {
+                Existing::Skip => {
+                    println!("  skip, already formatted");
+                    return Ok(true);
+                }
+                Existing::Check => {
+                    println!("  check already formatted template, nothing will be written");
+                    return Ok(true);
+                }
+                Existing::Reformat => {
+                    if let Some(reason) = reformat_blocker(formatted, &folder, &entries) {
+                        println!("  fail, {}", reason);
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+        if folder.exists() {
+            println!("  replace folder {:?}", folder);
+        } else {
+            println!("  create folder {:?}", folder);
+        }
+        println!("  unzip {} file(s)", entries.len());
+        let thumb_path = folder.join("thumb.jpg");
+        let sibling = file
+            .to_string_lossy()
+            .strip_suffix(".zip")
+            .map(|prefix| thumb::find_sibling(Path::new(prefix)))
+            .ok_or(anyhow!("strip filename failed"))?;
+        match sibling {
+            Some(image_path) => println!("  convert {:?} to {:?}", image_path, thumb_path),
+            None => println!("  generate {:?} from the largest image", thumb_path),
+        }
+        if !entries.contains_key("index.html") {
+            println!("  fail, index.html not found");
+            return Ok(false);
+        }
+        println!("  write {:?}", folder.join("template.html"));
+        println!("  write {:?}", folder.join("template.json"));
+        println!(
+            "  move {:?} to {:?}",
+            folder.join("index.html"),
+            folder.join(BACKUP_FOLDER).join("index.html")
+        );
+        Ok(true)
+    }
 
+    /// Print what compress mode would do to target folder.
+    fn compress_plan(&self, path: &Path) -> Result<()> {
+        let (path_name, out_path) = self.output_path(path)?;
+        let src_path = self.directory.join(&path_name);
+        if !src_path.exists() {
+            println!("Would write {:?} from {:?}", out_path, src_path);
+            return Ok(());
+        }
+        let files = WalkDir::new(&src_path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| {
+                e.path()
+                    .strip_prefix(&src_path)
+                    .is_ok_and(|name| !ziper::skipped(e.path(), name))
+            })
+            .count();
+        println!("Would write {:?} with {} file(s)", out_path, files);
+        Ok(())
+    }
+
+    /// Print what current mode would do, without changing anything.
+    fn plan(&self) -> Result<()> {
+        println!("Dry run, nothing will be changed\n");
         match self.mode {
             Mode::Format => {
-                self.file_list
-                    .par_iter()
-                    .map(|file| self.format_process(file))
-                    .collect::<Result<Vec<_>>>()?;
+                for file in &self.file_list {
+                    self.format_plan(file)?;
+                }
             }
+            Mode::Compress => {
+                if self.output.exists() {
+                    println!("Would remove output directory {:?}", self.output);
+                }
+                println!("Would create output directory {:?}", self.output);
+                for path in &self.file_list {
+                    self.compress_plan(path)?;
+                }
+            }
+            Mode::Upload => {
+                let http = self
+                    .http
+                    .as_ref()
+                    .ok_or(anyhow!("http client initial failed"))?;
+                for path in &self.file_list {
+                    http.plan(path, self.upload_name)?;
+                }
+            }
+            Mode::Revert => self
+                .file_list
+                .iter()
+                .for_each(|path| self.revert_plan(path)),
+            Mode::All => {
+                let http = self
+                    .http
+                    .as_ref()
+                    .ok_or(anyhow!("http client initial failed"))?;
+                if !self.output.exists() {
+                    println!("Would create output directory {:?}\n", self.output);
+                }
+                for file in &self.file_list {
+                    if !self.format_plan(file)? {
+                        println!("  compress and upload would be skipped\n");
+                        continue;
+                    }
+                    let file_name = file
+                        .file_name()
+                        .ok_or(anyhow!("convert filename failed"))?
+                        .to_string_lossy();
+                    let folder = self.directory.join(format_name(&file_name));
+                    self.compress_plan(&folder)?;
+                    http.plan(&self.output_path(&folder)?.1, self.upload_name)?;
+                    println!();
+                }
+            }
+            // check mode never changes anything
+            Mode::Check => unreachable!(),
+        }
+        Ok(())
+    }
+
+    /// Run `f` on every target, collect outcome of each target.
+    ///
+    /// Logs of every target are in a `template` span with the target name.
+    /// When `--fail-fast` is set, targets that have not started after the first
+    /// error are skipped.
+    fn run<F>(&self, f: F) -> Vec<Outcome>
+    where
+        F: Fn(&Path, &mut Outcome) -> Result<Status> + Sync,
+    {
+        use rayon::prelude::*;
+
+        self.progress.start(self.mode.name(), self.file_list.len());
+        let stopped = AtomicBool::new(false);
+        let outcomes = self
+            .file_list
+            .par_iter()
+            .map(|path| {
+                let mut outcome = Outcome::new(path, self.mode.stage());
+                if stopped.load(Ordering::Relaxed) {
+                    outcome.status = Status::Skipped("stopped by --fail-fast".to_string());
+                    self.progress.done(&outcome);
+                    return outcome;
+                }
+                // error level keeps the template context in quiet mode
+                let _span = error_span!("template", file = %outcome.name()).entered();
+                let start = Instant::now();
+                self.progress.stage(&outcome.name(), outcome.stage);
+                let result = f(path, &mut outcome);
+                if let Err(err) = &result {
+                    error!(stage = outcome.stage, "{:#}", err);
+                    if self.fail_fast {
+                        stopped.store(true, Ordering::Relaxed);
+                    }
+                }
+                outcome.finish(result, start.elapsed());
+                self.progress.done(&outcome);
+                outcome
+            })
+            .collect();
+        self.progress.finish();
+        outcomes
+    }
+
+    pub fn process(&self) -> Result<()> {
+        if self.dry_run && self.mode != Mode::Check {
+            return self.plan();
+        }
+        let start = Instant::now();
+        let outcomes = match self.mode {
+            Mode::Format => self.run(|file, outcome| self.format_outcome(file, outcome)),
             Mode::Compress => {
                 if self.output.exists() {
                     fs::remove_dir_all(&self.output)?;
                 }
                 fs::create_dir_all(&self.output)?;
-                self.file_list
-                    .par_iter()
-                    .map(|path| self.compress_process(path))
-                    .collect::<Result<Vec<_>>>()?;
+                self.run(|path, outcome| {
+                    outcome.outputs.push(self.compress_process(path)?);
+                    Ok(Status::Succeeded)
+                })
             }
-            Mode::Upload => {
-                self.file_list
-                    .par_iter()
-                    .map(|path| {
-                        self.http
-                            .as_ref()
-                            .ok_or(anyhow!("http client initial failed"))?
-                            .upload(path, self.upload_name)
-                    })
-                    .collect::<Result<Vec<_>>>()?;
+            Mode::Upload => self.run(|path, outcome| self.upload_outcome(path, outcome)),
+            Mode::All => {
+                fs::create_dir_all(&self.output)?;
+                self.run(|file, outcome| self.pipeline_process(file, outcome))
             }
+            Mode::Revert => self.run(|path, _| self.revert_process(path)),
+            Mode::Check => self.run(|file, _| {
+                let problems = check::check(file, &self.ziper, &self.rules);
+                if !problems.is_empty() {
+                    return Err(anyhow!(
+                        "{} problem(s):\n- {}",
+                        problems.len(),
+                        problems.join("\n- ")
+                    ));
+                }
+                Ok(Status::Succeeded)
+            }),
+        };
+
+        println!();
+        let failed = outcome::summary(&outcomes);
+        if let Some(path) = &self.report {
+            report::write(
+                path,
+                self.report_format,
+                self.mode,
+                &outcomes,
+                start.elapsed(),
+            )?;
+            info!("write report to {:?} done", path);
+        }
+        if failed > 0 {
+            return Err(anyhow!(
+                "{} of {} template(s) failed",
+                failed,
+                outcomes.len()
+            ));
         }
         Ok(())
     }
@@ -332,11 +901,7 @@ impl<'a> Sisyphus<'a> {
     ///
     /// - `path`: &Path document path
     fn _parse_html(&self, path: &Path) -> Result<Html> {
-        let mut index_file = File::options()
-            .read(true)
-            .write(true)
-            .append(true)
-            .open(path)?;
+        let mut index_file = File::options().read(true).append(true).open(path)?;
         let mut index = String::new();
         index_file.read_to_string(&mut index)?;
         let doc = Html::parse_document(&index);
@@ -344,44 +909,147 @@ impl<'a> Sisyphus<'a> {
     }
 }
 
-/// Build dom attributes
+/// Move file in template folder to backup folder when it exists.
 ///
-/// - `attr` attribute name
-/// - `value` attirbute value
+/// - `folder`: template folder
+/// - `name`: file name in template folder
+fn backup(folder: &Path, name: &str) -> Result<()> {
+    let source = folder.join(name);
+    if !source.is_file() {
+        return Ok(());
+    }
+    let backup = folder.join(BACKUP_FOLDER);
+    fs::create_dir_all(&backup)?;
+    fs::rename(&source, backup.join(name))
+        .with_context(|| anyhow!("cannot backup {:?} to {:?}", source, backup))
+}
+
+/// Where a template is already formatted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Formatted {
+    /// Template folder contains formatted files
+    Folder,
+    /// Zip contains `template.html` instead of `index.html`
+    Zip,
+}
+
+/// Detect target template is already formatted.
 ///
-/// `data-template="img"`: `attr_builder("data-template", "img");`
-fn attr_builder<'a>(attr: &'a str, value: &'a str) -> (QualName, Tendril<UTF8>) {
-    let data = QualName {
-        prefix: None,
-        ns: Namespace::from(""),
-        local: LocalName::from(attr),
-    };
-    let data_v: Tendril<UTF8> = Tendril::from(value);
-    (data, data_v)
+/// - `folder`: folder that zip unzip to
+/// - `entries`: files in zip
+fn formatted(folder: &Path, entries: &HashMap<String, Vec<u8>>) -> Option<Formatted> {
+    if folder.join("template.html").exists() || folder.join("template.json").exists() {
+        Some(Formatted::Folder)
+    } else if !entries.contains_key("index.html") && entries.contains_key("template.html") {
+        Some(Formatted::Zip)
+    } else {
+        None
+    }
 }
 
-/// Add attributes to target element
+/// Detect files in existing folder differ from files in zip.
 ///
-/// - `element` target element
-/// - `attr` attribute
-/// - `value` attribute value
+/// Files that format mode writes, such as `thumb.jpg` and backup folder, are not compared.
 ///
-/// `<div data-template="img"></div>`: `add_attr(div_element, "data-template", "img");`
-fn add_attr<'a>(element: &mut scraper::node::Element, attr: &'a str, value: &'a str) {
-    let (data_img, data_v) = attr_builder(attr, value);
-    element.attrs.insert(data_img, data_v);
+/// - `folder`: folder that zip unzip to
+/// - `entries`: files in zip
+fn diverged(folder: &Path, entries: &HashMap<String, Vec<u8>>) -> bool {
+    if !folder.is_dir() {
+        return false;
+    }
+    let generated = |name: &str| {
+        GENERATED_FILES.contains(&name) || name.split('/').next() == Some(BACKUP_FOLDER)
+    };
+    let mut files = 0;
+    for entry in WalkDir::new(folder)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let Ok(name) = entry.path().strip_prefix(folder) else {
+            continue;
+        };
+        let name = name.to_string_lossy().replace('\\', "/");
+        if generated(&name) {
+            continue;
+        }
+        files += 1;
+        match entries.get(&name) {
+            Some(content) if fs::read(entry.path()).is_ok_and(|c| &c == content) => {}
+            _ => return true,
+        }
+    }
+    files != entries.keys().filter(|name| !generated(name)).count()
+}
+
+/// Reason that formatted template cannot be formatted again.
+fn reformat_blocker(
+    formatted: Formatted,
+    folder: &Path,
+    entries: &HashMap<String, Vec<u8>>,
+) -> Option<String> {
+    if !entries.contains_key("index.html") {
+        return Some("index.html not found in zip".to_string());
+    }
+    (formatted == Formatted::Folder && edited(folder)).then(|| {
+        format!(
+            "{:?} has been edited after format, remove it to reformat",
+            folder
+        )
+    })
 }
 
-/// Detect trget text is vaild text
+/// Detect files in formatted folder have been modified after format.
 ///
-/// <div class="text-wrapper">人才发展</div>
-fn vaild_text(text: &str) -> bool {
-    let t = text.split('\n').collect::<Vec<_>>();
-    let is_vaild = t.iter().any(|text| !text.trim().is_empty());
-    // if is_vaild {
-    //     println!("Process text node {}", t.join(''));
-    // }
-    is_vaild
+/// Format mode records checksums of every file when it's done, folders without
+/// checksums are treated as edited.
+fn edited(folder: &Path) -> bool {
+    let recorded = fs::read(folder.join(BACKUP_FOLDER).join(CHECKSUMS_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_slice::<BTreeMap<String, String>>(&c).ok());
+    match (recorded, checksums(folder)) {
+        (Some(recorded), Ok(current)) => recorded != current,
+        _ => true,
+    }
+}
+
+/// SHA-256 of every file in template folder by relative path, backup folder is not included.
+fn checksums(folder: &Path) -> Result<BTreeMap<String, String>> {
+    let mut checksums = BTreeMap::new();
+    for entry in WalkDir::new(folder) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let name = entry
+            .path()
+            .strip_prefix(folder)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if name.split('/').next() == Some(BACKUP_FOLDER) {
+            continue;
+        }
+        let content = fs::read(entry.path())?;
+        checksums.insert(name, format!("{:x}", Sha256::digest(&content)));
+    }
+    Ok(checksums)
+}
+
+/// Unique class of scoped template root element
+///
+/// `A002_GG42_1100X600` => `eris-a002_gg42_1100x600`
+fn scope_class(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    format!("eris-{}", name)
 }
 
 /// Format filename with extention
@@ -398,42 +1066,6 @@ fn format_name(file_name: &str) -> &str {
     }
 }
 
-/// recursion target node to find node that's contain target text.
-/// And add attributes
-///
-/// - `target`: target node
-/// - `html`: whole mutable html string
-fn traverse_node(target: &NodeRef<Node>, html: &mut String) -> Result<()> {
-    let v = target.value();
-    if v.is_text() {
-        let is_vaild = vaild_text(&v.as_text().map(|t| t.to_string()).unwrap_or_default());
-        if is_vaild {
-            let parent = target
-                .parent()
-                .ok_or(anyhow!("cannot find {:?} parent", target))?;
-            let text = parent
-                .value()
-                .as_element()
-                .ok_or(anyhow!("cannot parse element"))?;
-            let old_h = format!("{:?}", text);
-            println!("Processing text node {}", old_h);
-
-            let mut new_text = text.clone();
-            add_attr(&mut new_text, "data-template", "text");
-            add_attr(&mut new_text, "data-title", "标题");
-
-            let new_h = format!("{:?}", new_text);
-            println!("Processed text node {}", new_h);
-            *html = html.replace(&old_h, &new_h);
-        }
-    } else {
-        for child in target.children() {
-            traverse_node(&child, html)?
-        }
-    }
-    Ok(())
-}
-
 /// Format path in user input directory.
 ///
 /// When `use_file = false` will not return folder that's name equal to `output`.
@@ -453,7 +1085,7 @@ fn format_path(path: DirEntry, use_file: bool) -> ErisResult<PathBuf> {
         let path = path.path();
         let dir_name = path
             .iter()
-            .last()
+            .next_back()
             .ok_or(anyhow!("Error: cannot read folder name"))?;
         if dir_name == "output" {
             Err(ErisError::Empty(String::new()))
@@ -464,3 +1096,127 @@ fn format_path(path: DirEntry, use_file: bool) -> ErisResult<PathBuf> {
         Err(ErisError::Empty(String::new()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create template folder with files in temp directory.
+    fn folder(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let folder = std::env::temp_dir()
+            .join(format!("eris-sisyphus-{}", std::process::id()))
+            .join(name);
+        if folder.exists() {
+            fs::remove_dir_all(&folder).unwrap();
+        }
+        for (file, content) in files {
+            let path = folder.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        folder
+    }
+
+    fn entries(files: &[(&str, &str)]) -> HashMap<String, Vec<u8>> {
+        files
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.as_bytes().to_vec()))
+            .collect()
+    }
+
+    /// Record checksums like format mode does.
+    fn record(folder: &Path) {
+        let backup = folder.join(BACKUP_FOLDER);
+        fs::create_dir_all(&backup).unwrap();
+        let checksums = serde_json::to_string(&checksums(folder).unwrap()).unwrap();
+        fs::write(backup.join(CHECKSUMS_FILE), checksums).unwrap();
+    }
+
+    const ZIP: [(&str, &str); 3] = [
+        ("index.html", "<body>a</body>"),
+        ("style.css", ""),
+        ("img/a.png", "png"),
+    ];
+
+    #[test]
+    fn folder_differs_from_zip() {
+        let entries = entries(&ZIP);
+        assert!(!diverged(&folder("missing", &[]).join("x"), &entries));
+
+        let same = folder("same", &[ZIP[0], ZIP[1], ZIP[2], ("thumb.jpg", "jpg")]);
+        assert!(formatted(&same, &entries).is_none());
+        assert!(!diverged(&same, &entries));
+
+        let edited = folder(
+            "edited",
+            &[("index.html", "<body>b</body>"), ZIP[1], ZIP[2]],
+        );
+        assert!(diverged(&edited, &entries));
+
+        let added = folder("added", &[ZIP[0], ZIP[1], ZIP[2], ("img/b.png", "png")]);
+        assert!(diverged(&added, &entries));
+
+        let removed = folder("removed", &[ZIP[0], ZIP[1]]);
+        assert!(diverged(&removed, &entries));
+    }
+
+    #[test]
+    fn formatted_and_edited() {
+        let entries = entries(&ZIP);
+        let folder = folder(
+            "formatted-edited",
+            &[
+                ZIP[1],
+                ZIP[2],
+                ("template.html", "<p>a</p>"),
+                ("template.json", "{}"),
+            ],
+        );
+        record(&folder);
+        assert_eq!(formatted(&folder, &entries), Some(Formatted::Folder));
+        assert!(reformat_blocker(Formatted::Folder, &folder, &entries).is_none());
+
+        // same size, mtime may not change on coarse timestamps
+        fs::write(folder.join("template.html"), "<p>b</p>").unwrap();
+        assert!(edited(&folder));
+        assert!(reformat_blocker(Formatted::Folder, &folder, &entries).is_some());
+
+        fs::write(folder.join("template.html"), "<p>a</p>").unwrap();
+        fs::write(folder.join("img/b.png"), "png").unwrap();
+        assert!(edited(&folder));
+    }
+
+    #[test]
+    fn formatted_and_unedited() {
+        let entries = entries(&ZIP);
+        let folder = folder(
+            "formatted-unedited",
+            &[
+                ZIP[1],
+                ZIP[2],
+                ("template.html", "<p>a</p>"),
+                ("template.json", "{}"),
+            ],
+        );
+        fs::create_dir_all(folder.join(BACKUP_FOLDER)).unwrap();
+        fs::write(folder.join(BACKUP_FOLDER).join("index.html"), ZIP[0].1).unwrap();
+        record(&folder);
+        assert!(!edited(&folder));
+        assert!(reformat_blocker(Formatted::Folder, &folder, &entries).is_none());
+
+        // formatted before checksums are recorded
+        fs::remove_file(folder.join(BACKUP_FOLDER).join(CHECKSUMS_FILE)).unwrap();
+        assert!(edited(&folder));
+    }
+
+    #[test]
+    fn zip_only_has_template_html() {
+        let entries = entries(&[("template.html", "<p>a</p>"), ("style.css", "")]);
+        let folder = folder("zip-only", &[]);
+        assert_eq!(formatted(&folder, &entries), Some(Formatted::Zip));
+        assert_eq!(
+            reformat_blocker(Formatted::Zip, &folder, &entries).as_deref(),
+            Some("index.html not found in zip")
+        );
+    }
+}
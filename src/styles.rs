@@ -0,0 +1,259 @@
+use std::{io, path::Path, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use scraper::{Html, Selector};
+
+use crate::{
+    css::{self, AtRule, Block, Item, Stylesheet},
+    utils::{is_local_url, normalize, url_path},
+};
+
+/// Read file content by path relative to template folder.
+pub type ReadFile<'a> = &'a dyn Fn(&Path) -> io::Result<String>;
+
+/// Collected template stylesheet.
+#[derive(Debug)]
+pub struct Styles {
+    pub stylesheet: Stylesheet,
+    /// Stylesheets that cannot be resolved locally
+    pub warnings: Vec<String>,
+}
+
+struct Collector<'a> {
+    read: ReadFile<'a>,
+    /// Loaded stylesheets, relative to template folder
+    loaded: Vec<PathBuf>,
+    warnings: Vec<String>,
+}
+
+/// Collect all stylesheets of template in document order.
+///
+/// `<link rel="stylesheet">` and `<style>` in `<head>` are inlined, `@import`
+/// rules are resolved, `url()` references are rebased to template folder.
+/// Sheets with `media` are wrapped in `@media`, alternate stylesheets are skipped.
+/// `style.css` is appended when it's not linked.
+///
+/// - `doc`: parsed `index.html`
+/// - `read`: read file in template folder
+pub fn collect(doc: &Html, read: ReadFile) -> Result<Styles> {
+    let selector = Selector::parse("head link, head style")
+        .map_err(|err| anyhow!("cannot create selector {}", err))?;
+
+    let mut collector = Collector {
+        read,
+        loaded: vec![],
+        warnings: vec![],
+    };
+    let mut stylesheet = Stylesheet::default();
+    for element in doc.select(&selector) {
+        let value = element.value();
+        if value.name() == "style" {
+            let style = element.text().collect::<String>();
+            let style = collector.resolve(Stylesheet::parse(&style), Path::new(""));
+            let media = value.attr("media").unwrap_or_default().trim();
+            stylesheet.items.extend(with_media(style.items, media));
+            continue;
+        }
+        // `alternate stylesheet` is not applied until user picks it
+        let rel = value.attr("rel").unwrap_or_default();
+        let has_rel = |name: &str| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case(name));
+        if !has_rel("stylesheet") || has_rel("alternate") {
+            continue;
+        }
+        let Some(href) = value.attr("href") else {
+            continue;
+        };
+        let Some(linked) = collector.load(href, Path::new("")) else {
+            continue;
+        };
+        let media = value.attr("media").unwrap_or_default().trim();
+        stylesheet.items.extend(with_media(linked.items, media));
+    }
+
+    let style_path = PathBuf::from("style.css");
+    if !collector.loaded.contains(&style_path) {
+        let style_file = read(&style_path).context("cannot read style.css")?;
+        collector.loaded.push(style_path);
+        let style = collector.resolve(Stylesheet::parse(&style_file), Path::new(""));
+        stylesheet.items.extend(style.items);
+    }
+    Ok(Styles {
+        stylesheet,
+        warnings: collector.warnings,
+    })
+}
+
+impl Collector<'_> {
+    /// Load a local stylesheet, record warning when it cannot be resolved.
+    ///
+    /// - `href`: stylesheet url
+    /// - `base`: directory of the referencing stylesheet, relative to template folder
+    fn load(&mut self, href: &str, base: &Path) -> Option<Stylesheet> {
+        if !is_local_url(href) {
+            self.warnings
+                .push(format!("cannot inline stylesheet {}", href));
+            return None;
+        }
+        let path = normalize(&base.join(url_path(href)));
+        if self.loaded.contains(&path) {
+            return None;
+        }
+        let content = match (self.read)(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                self.warnings
+                    .push(format!("cannot read stylesheet {:?} {}", path, err));
+                return None;
+            }
+        };
+        self.loaded.push(path.clone());
+
+        let base = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut stylesheet = Stylesheet::parse(&content);
+        // rebase `url()` references to template folder before resolving imports,
+        // imported stylesheets are already rebased, `@import` preludes are not rewritten
+        if !base.as_os_str().is_empty() {
+            stylesheet.rewrite_urls(&|url| {
+                is_local_url(url).then(|| {
+                    normalize(&base.join(url))
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+            });
+        }
+        Some(self.resolve(stylesheet, &base))
+    }
+
+    /// Replace top level `@import` rules with imported stylesheets.
+    fn resolve(&mut self, stylesheet: Stylesheet, base: &Path) -> Stylesheet {
+        let mut items = vec![];
+        for item in stylesheet.items {
+            let Item::AtRule(AtRule { name, prelude, .. }) = &item else {
+                items.push(item);
+                continue;
+            };
+            if name != "import" {
+                items.push(item);
+                continue;
+            }
+            let Some((href, media)) = import_target(prelude) else {
+                self.warnings
+                    .push(format!("cannot resolve @import {}", prelude));
+                continue;
+            };
+            let Some(imported) = self.load(&href, base) else {
+                continue;
+            };
+            items.extend(with_media(imported.items, &media));
+        }
+        Stylesheet { items }
+    }
+}
+
+/// Wrap items in `@media` rule, items are kept as is when media is empty or `all`.
+///
+/// - `items`: items of linked or imported stylesheet
+/// - `media`: media query of `<link media>` or `@import`
+fn with_media(items: Vec<Item>, media: &str) -> Vec<Item> {
+    if media.is_empty() || media.eq_ignore_ascii_case("all") {
+        return items;
+    }
+    vec![Item::AtRule(AtRule {
+        name: "media".to_string(),
+        prelude: media.to_string(),
+        block: Some(Block::Rules(items)),
+    })]
+}
+
+/// Parse `@import` prelude to url and media query.
+///
+/// `url("a.css") screen` => `("a.css", "screen")`
+fn import_target(prelude: &str) -> Option<(String, String)> {
+    let prelude = prelude.trim();
+    if prelude.to_ascii_lowercase().starts_with("url(") {
+        let end = prelude.find(')')?;
+        let href = css::urls(&prelude[..=end]).into_iter().next()?;
+        return Some((href, prelude[end + 1..].trim().to_string()));
+    }
+    let quote = prelude.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let end = prelude[1..].find(quote)? + 1;
+    Some((
+        prelude[1..end].to_string(),
+        prelude[end + 1..].trim().to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn collect_files(index: &str, files: &[(&str, &str)]) -> Styles {
+        let files = files
+            .iter()
+            .map(|(path, content)| (PathBuf::from(path), content.to_string()))
+            .collect::<HashMap<_, _>>();
+        let doc = Html::parse_document(index);
+        collect(&doc, &|p| {
+            files
+                .get(p)
+                .cloned()
+                .ok_or(io::Error::new(io::ErrorKind::NotFound, "not found"))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn nested_import_urls_are_rebased_once() {
+        let styles = collect_files(
+            r#"<head><link rel="stylesheet" href="css/a.css"></head>"#,
+            &[
+                ("style.css", ""),
+                (
+                    "css/a.css",
+                    r#"@import "sub/b.css"; .a { background: url(a.png) }"#,
+                ),
+                ("css/sub/b.css", ".b { background: url(../../img/x.png) }"),
+            ],
+        );
+        assert!(styles.warnings.is_empty());
+        assert_eq!(styles.stylesheet.urls(), vec!["img/x.png", "css/a.png"]);
+    }
+
+    #[test]
+    fn link_media_is_kept() {
+        let styles = collect_files(
+            r#"<head>
+            <link rel="stylesheet" href="print.css" media="print">
+            <link rel="stylesheet" href="all.css" media="all">
+            <link rel="alternate stylesheet" href="dark.css">
+            </head>"#,
+            &[
+                ("style.css", ""),
+                ("print.css", ".p { color: red }"),
+                ("all.css", ".a { color: blue }"),
+                ("dark.css", ".d { color: black }"),
+            ],
+        );
+        assert_eq!(
+            styles.stylesheet.to_string(),
+            Stylesheet::parse("@media print { .p { color: red } } .a { color: blue }").to_string()
+        );
+    }
+
+    #[test]
+    fn import_media_is_kept() {
+        let styles = collect_files(
+            "<head></head>",
+            &[
+                ("style.css", r#"@import url("print.css") print;"#),
+                ("print.css", ".p { color: red }"),
+            ],
+        );
+        assert_eq!(
+            styles.stylesheet.to_string(),
+            Stylesheet::parse("@media print { .p { color: red } }").to_string()
+        );
+    }
+}
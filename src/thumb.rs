@@ -0,0 +1,202 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, GenericImageView, ImageReader,
+};
+use tracing::{debug, warn};
+use walkdir::WalkDir;
+
+/// Image extensions that can be used as thumbnail source.
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "gif"];
+/// Extensions of thumbnail that has same name with template zip.
+const THUMB_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+/// JPEG quality of thumbnail.
+const THUMB_QUALITY: u8 = 85;
+/// Allowed difference between thumbnail and template aspect ratio.
+const RATIO_TOLERANCE: f64 = 0.02;
+
+/// Find thumbnail that has same name with template zip.
+///
+/// `A002_GG42_1100X600.zip` => `A002_GG42_1100X600.png`
+///
+/// - `folder`: template folder, the zip path without `.zip`
+pub fn find_sibling(folder: &Path) -> Option<PathBuf> {
+    THUMB_EXTENSIONS
+        .iter()
+        .flat_map(|ext| [ext.to_string(), ext.to_uppercase()])
+        .map(|ext| {
+            let mut p = folder.as_os_str().to_owned();
+            p.push(".");
+            p.push(ext);
+            PathBuf::from(p)
+        })
+        .find(|p| p.is_file())
+}
+
+/// Validate thumbnail and convert it to a standard JPEG.
+///
+/// Format is detected from file content, corrupt images are rejected.
+/// When aspect ratio is different from template size, print warning and crop
+/// the center of image to template aspect ratio.
+///
+/// - `source`: thumbnail that has same name with template zip
+/// - `size`: template size from filename
+/// - `width`: thumbnail width
+/// - `target`: thumbnail path
+pub fn normalize(source: &Path, size: Option<(u32, u32)>, width: u32, target: &Path) -> Result<()> {
+    let img = decode(source)?;
+    if let Some((w, h)) = size {
+        let (img_w, img_h) = img.dimensions();
+        if !ratio_matches((img_w, img_h), (w, h)) {
+            warn!(
+                "thumb {:?} size {}x{} does not match template size {}x{}",
+                source, img_w, img_h, w, h
+            );
+            return save_jpeg(&crop_to_ratio(&img, w, h), width, size, target);
+        }
+    }
+    save_jpeg(&img, width, size, target)
+}
+
+/// Decode image by its content.
+pub fn decode(source: &Path) -> Result<DynamicImage> {
+    let reader = ImageReader::open(source)
+        .with_context(|| anyhow!("cannot open image {:?}", source))?
+        .with_guessed_format()
+        .with_context(|| anyhow!("cannot read image {:?}", source))?;
+    if reader.format().is_none() {
+        return Err(anyhow!("{:?} is not a supported image", source));
+    }
+    reader
+        .decode()
+        .with_context(|| anyhow!("image {:?} is truncated or corrupt", source))
+}
+
+/// Detect aspect ratio of `size` matches `expected`.
+pub fn ratio_matches(size: (u32, u32), expected: (u32, u32)) -> bool {
+    let (w, h) = size;
+    let (ew, eh) = expected;
+    if h == 0 || eh == 0 {
+        return false;
+    }
+    let ratio = w as f64 / h as f64;
+    let expected = ew as f64 / eh as f64;
+    ((ratio - expected) / expected).abs() <= RATIO_TOLERANCE
+}
+
+/// Generate thumbnail from the largest image in template folder.
+///
+/// The image is cropped to the aspect ratio of template size and scaled to `width`.
+///
+/// - `folder`: template folder
+/// - `size`: template size from filename
+/// - `width`: thumbnail width
+/// - `target`: thumbnail path
+pub fn generate(folder: &Path, size: Option<(u32, u32)>, width: u32, target: &Path) -> Result<()> {
+    let source = largest_image(folder, target).ok_or(anyhow!(
+        "cannot find any image in {:?} to generate thumb",
+        folder
+    ))?;
+    debug!("generate thumb from {:?}", &source);
+    let img = decode(&source)?;
+    let img = match size {
+        Some((w, h)) => crop_to_ratio(&img, w, h),
+        None => img,
+    };
+    save_jpeg(&img, width, size, target)
+}
+
+/// Find the image that has the most pixels in folder.
+///
+/// - `folder`: template folder
+/// - `exclude`: thumbnail path, it's not a source image
+fn largest_image(folder: &Path, exclude: &Path) -> Option<PathBuf> {
+    WalkDir::new(folder)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path() != exclude)
+        .filter(|e| {
+            e.path().extension().is_some_and(|ext| {
+                IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str())
+            })
+        })
+        .filter_map(|e| {
+            let (w, h) = image::image_dimensions(e.path()).ok()?;
+            Some((w as u64 * h as u64, e.into_path()))
+        })
+        .max_by_key(|(area, _)| *area)
+        .map(|(_, path)| path)
+}
+
+/// Crop the center of image to `width:height` aspect ratio.
+fn crop_to_ratio(img: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let (w, h) = img.dimensions();
+    if width == 0 || height == 0 || w == 0 || h == 0 {
+        return img.clone();
+    }
+    // compare w / h with width / height
+    let (crop_w, crop_h) = if w as u64 * height as u64 > h as u64 * width as u64 {
+        ((h as u64 * width as u64 / height as u64) as u32, h)
+    } else {
+        (w, (w as u64 * height as u64 / width as u64) as u32)
+    };
+    img.crop_imm(
+        (w - crop_w) / 2,
+        (h - crop_h) / 2,
+        crop_w.max(1),
+        crop_h.max(1),
+    )
+}
+
+/// Scale image to `width` and save as JPEG.
+///
+/// Height is calculated from template size, or from image when size is unknown.
+fn save_jpeg(
+    img: &DynamicImage,
+    width: u32,
+    size: Option<(u32, u32)>,
+    target: &Path,
+) -> Result<()> {
+    let (w, h) = match size {
+        Some((w, h)) if w > 0 && h > 0 => (w, h),
+        _ => img.dimensions(),
+    };
+    let height = ((h as u64 * width as u64) / w.max(1) as u64).max(1) as u32;
+    let thumb = img
+        .resize_exact(width, height, FilterType::Lanczos3)
+        .to_rgb8();
+    let file = File::create(target).with_context(|| anyhow!("cannot create thumb {:?}", target))?;
+    JpegEncoder::new_with_quality(BufWriter::new(file), THUMB_QUALITY)
+        .encode_image(&thumb)
+        .with_context(|| anyhow!("cannot write thumb {:?}", target))?;
+    debug!("write thumb to {:?} done", target);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbImage;
+
+    use super::*;
+
+    #[test]
+    fn normalize_crops_to_template_ratio() {
+        let dir = std::env::temp_dir().join(format!("eris-thumb-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("A002_GG42_1100X600.png");
+        let target = dir.join("thumb.jpg");
+        RgbImage::new(500, 500).save(&source).unwrap();
+
+        normalize(&source, Some((1100, 600)), 440, &target).unwrap();
+        assert_eq!(image::image_dimensions(&target).unwrap(), (440, 240));
+
+        normalize(&source, None, 440, &target).unwrap();
+        assert_eq!(image::image_dimensions(&target).unwrap(), (440, 440));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Parse template size from filename.
+///
+/// `A002_GG42_1100X600.zip` => `Some((1100, 600))`
+///
+/// - `file_name` template filename, with or without extension
+pub fn template_size(file_name: &str) -> Option<(u32, u32)> {
+    let name = file_name.strip_suffix(".zip").unwrap_or(file_name);
+    let size = name.split('_').next_back()?.to_lowercase();
+    let (width, height) = size.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Detect target url is a local relative path.
+///
+/// Urls with scheme, protocol relative urls, absolute paths and fragments are not local.
+pub fn is_local_url(url: &str) -> bool {
+    let url = url.trim();
+    if url.is_empty() || url.starts_with('/') || url.starts_with('#') {
+        return false;
+    }
+    // `http:`, `data:`, `javascript:`
+    let scheme = url.split_once(':').map(|(scheme, _)| scheme);
+    !scheme.is_some_and(|s| {
+        s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+    })
+}
+
+/// Strip query and fragment of url.
+pub fn url_path(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+/// Lexically normalize path, resolve `.` and `..` components.
+///
+/// `css/../img/a.png` => `img/a.png`
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push("..");
+                }
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
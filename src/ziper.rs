@@ -1,14 +1,16 @@
 use std::{
+    collections::HashMap,
     fs::{self, File},
     io::{self, Read, Seek, Write},
     path::{Path, PathBuf},
 };
 
 use anyhow::Result;
+use tracing::trace;
 use walkdir::DirEntry;
 use zip::{write::FileOptions, ZipArchive};
 
-use crate::consts::{SKIP_FILES, SKIP_FOLDERS};
+use crate::consts::{BACKUP_FOLDER, SKIP_FILES, SKIP_FOLDERS};
 
 #[derive(Debug)]
 pub struct Ziper {}
@@ -43,17 +45,12 @@ impl Ziper {
             // Write file or directory explicitly
             // Some unzip tools unzip files with directory paths correctly, some do not!
             if path.is_file() {
-                let filename = path
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_str()
-                    .unwrap_or_default();
-                if SKIP_FILES.contains(&filename) {
-                    println!("Skipping file {path:?}");
+                if skipped(path, name) {
+                    trace!("skip file {path:?}");
                     continue;
                 }
 
-                println!("Adding file {path:?} as {name:?} ...");
+                trace!("add file {path:?} as {name:?}");
                 zip.start_file(name.to_string_lossy(), options)?;
 
                 let mut f = File::open(path)?;
@@ -61,13 +58,13 @@ impl Ziper {
                 zip.write_all(&buffer)?;
                 buffer.clear();
             } else if !name.as_os_str().is_empty() {
-                if SKIP_FOLDERS.contains(&name.to_str().unwrap_or_default()) {
-                    println!("Skipping dir {name:?}");
+                if skipped(path, name) {
+                    trace!("skip dir {name:?}");
                     continue;
                 }
                 // Only if not root! Avoids path spec / warning
                 // and mapname conversion failed error on unzip
-                println!("Adding dir {path:?} as {name:?} ...");
+                trace!("add dir {path:?} as {name:?}");
                 zip.add_directory(name.to_string_lossy(), options)?;
             }
         }
@@ -75,6 +72,30 @@ impl Ziper {
         Ok(())
     }
 
+    /// Read all files in zip to memory.
+    ///
+    /// Keys are file paths in zip with `/` separator.
+    pub fn entries(&self, path: &Path) -> Result<HashMap<String, Vec<u8>>> {
+        let file = File::open(path)?;
+        let mut archive = ZipArchive::new(file)?;
+
+        let mut entries = HashMap::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            let Some(name) = file.enclosed_name() else {
+                continue;
+            };
+            let name = name.to_string_lossy().replace('\\', "/");
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer)?;
+            entries.insert(name, buffer);
+        }
+        Ok(entries)
+    }
+
     pub fn unzip(&self, prefix: Option<&str>, path: &Path) -> Result<()> {
         let file = File::open(path)?;
         let mut archive = ZipArchive::new(file)?;
@@ -95,13 +116,13 @@ impl Ziper {
             };
 
             if file.name().ends_with('/') {
-                println!("File {} extracted to \"{}\"", i, outpath.display());
+                trace!("file {} extracted to {:?}", i, outpath);
                 fs::create_dir_all(&outpath)?;
             } else {
-                println!(
-                    "File {} extracted to \"{}\" ({} bytes)",
+                trace!(
+                    "file {} extracted to {:?} ({} bytes)",
                     i,
-                    outpath.display(),
+                    outpath,
                     file.size()
                 );
                 if let Some(p) = outpath.parent() {
@@ -116,3 +137,55 @@ impl Ziper {
         Ok(())
     }
 }
+
+/// Detect target entry will not be added to zip.
+///
+/// Everything in the backup folder of template is skipped.
+///
+/// - `path`: entry path
+/// - `name`: entry path in zip
+pub fn skipped(path: &Path, name: &Path) -> bool {
+    if name.iter().next() == Some(BACKUP_FOLDER.as_ref()) {
+        return true;
+    }
+    if path.is_file() {
+        let filename = path
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default();
+        SKIP_FILES.contains(&filename)
+    } else {
+        SKIP_FOLDERS.contains(&name.to_str().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_backup_folder_is_pruned() {
+        let dir = std::env::temp_dir().join(format!("eris-skipped-{}", std::process::id()));
+        for file in [
+            "static/a.png",
+            "img/static/b.png",
+            ".eris/index.html",
+            "style.css",
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let skipped = |name: &str| skipped(&dir.join(name), Path::new(name));
+
+        assert!(skipped("static"));
+        assert!(!skipped("static/a.png"));
+        assert!(!skipped("img/static"));
+        assert!(!skipped("img/static/b.png"));
+        assert!(skipped(".eris"));
+        assert!(skipped(".eris/index.html"));
+        assert!(skipped("style.css"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}